@@ -0,0 +1,211 @@
+use crate::{
+    expr::{self, Expr},
+    stmt::{self, Stmt},
+    token::LiteralType,
+};
+
+/// Prints expressions in prefix notation like `(+ 1 (* 2 3))`
+///
+/// Statements are printed without the statements they contain, e.g. `(while (< i 3))`,
+/// because those get printed on their own when tracing.
+pub struct AstPrinter;
+
+impl AstPrinter {
+    pub fn print_expr(&mut self, expr: &Expr) -> String {
+        expr.accept(self)
+    }
+
+    pub fn print_stmt(&mut self, stmt: &Stmt) -> String {
+        stmt.accept(self)
+    }
+
+    fn parenthesize(&mut self, name: &str, exprs: &[&Expr]) -> String {
+        let mut result = format!("({name}");
+        for expr in exprs {
+            result.push(' ');
+            result.push_str(&self.print_expr(expr));
+        }
+        result.push(')');
+        result
+    }
+}
+
+impl expr::Visitor<String> for AstPrinter {
+    fn visit_assignment(&mut self, expr: &expr::Assignment) -> String {
+        self.parenthesize(&format!("= {}", expr.name.lexeme), &[&expr.value])
+    }
+
+    fn visit_binary(&mut self, expr: &expr::Binary) -> String {
+        self.parenthesize(&expr.operator.lexeme, &[&expr.left, &expr.right])
+    }
+
+    fn visit_call(&mut self, expr: &expr::Call) -> String {
+        let mut exprs = vec![expr.callee.as_ref()];
+        exprs.extend(expr.arguments.iter());
+        self.parenthesize("call", &exprs)
+    }
+
+    fn visit_get(&mut self, expr: &expr::Get) -> String {
+        let operator = if expr.optional { "?." } else { "." };
+        self.parenthesize(&format!("{operator} {}", expr.name.lexeme), &[&expr.object])
+    }
+
+    fn visit_grouping(&mut self, expr: &expr::Grouping) -> String {
+        self.parenthesize("group", &[&expr.expr])
+    }
+
+    fn visit_index(&mut self, expr: &expr::Index) -> String {
+        self.parenthesize("[]", &[&expr.object, &expr.index])
+    }
+
+    fn visit_interpolation(&mut self, expr: &expr::Interpolation) -> String {
+        let parts: Vec<&Expr> = expr.parts.iter().collect();
+        self.parenthesize("interpolation", &parts)
+    }
+
+    fn visit_list(&mut self, expr: &expr::List) -> String {
+        let elements: Vec<&Expr> = expr.elements.iter().collect();
+        self.parenthesize("list", &elements)
+    }
+
+    fn visit_literal(&self, expr: &expr::Literal) -> String {
+        match &expr.value {
+            LiteralType::String(s) => format!("{s:?}"),
+            #[cfg(feature = "bigint")]
+            LiteralType::BigInt(n) => format!("{n}n"),
+            value => value.to_string(),
+        }
+    }
+
+    fn visit_logical(&mut self, expr: &expr::Logical) -> String {
+        self.parenthesize(&expr.operator.lexeme, &[&expr.left, &expr.right])
+    }
+
+    fn visit_unary(&mut self, expr: &expr::Unary) -> String {
+        self.parenthesize(&expr.operator.lexeme, &[&expr.right])
+    }
+
+    fn visit_optional_chain(&mut self, expr: &expr::OptionalChain) -> String {
+        self.parenthesize("optional", &[&expr.expr])
+    }
+
+    fn visit_unwrap(&mut self, expr: &expr::Unwrap) -> String {
+        self.parenthesize("unwrap", &[&expr.expr])
+    }
+
+    fn visit_set(&mut self, expr: &expr::Set) -> String {
+        self.parenthesize(
+            &format!(".= {}", expr.name.lexeme),
+            &[&expr.object, &expr.value],
+        )
+    }
+
+    fn visit_set_index(&mut self, expr: &expr::SetIndex) -> String {
+        self.parenthesize("[]=", &[&expr.object, &expr.index, &expr.value])
+    }
+
+    fn visit_slice(&mut self, expr: &expr::Slice) -> String {
+        let bound = |bound: &Option<Box<Expr>>, printer: &mut Self| match bound {
+            Some(bound) => printer.print_expr(bound),
+            None => String::from("_"),
+        };
+        let start = bound(&expr.start, self);
+        let end = bound(&expr.end, self);
+        format!("([:] {} {start} {end})", self.print_expr(&expr.object))
+    }
+
+    fn visit_self_expr(&mut self, _: &expr::SelfExpr) -> String {
+        String::from("self")
+    }
+
+    fn visit_super_expr(&mut self, expr: &expr::SuperExpr) -> String {
+        format!("(super {})", expr.method.lexeme)
+    }
+
+    fn visit_variable(&mut self, expr: &expr::Variable) -> String {
+        expr.name.lexeme.clone()
+    }
+}
+
+impl stmt::Visitor<String> for AstPrinter {
+    fn visit_block(&mut self, _: &stmt::Block) -> String {
+        String::from("(block)")
+    }
+
+    fn visit_break(&mut self, stmt: &stmt::Break) -> String {
+        match &stmt.label {
+            Some(label) => format!("(break {})", label.lexeme),
+            None => String::from("(break)"),
+        }
+    }
+
+    fn visit_class(&mut self, stmt: &stmt::Class) -> String {
+        match &stmt.superclass {
+            Some(superclass) => {
+                self.parenthesize(&format!("class {} <", stmt.name.lexeme), &[superclass])
+            }
+            None => format!("(class {})", stmt.name.lexeme),
+        }
+    }
+
+    fn visit_expression(&mut self, stmt: &stmt::Expression) -> String {
+        self.parenthesize(";", &[&stmt.expr])
+    }
+
+    fn visit_if(&mut self, stmt: &stmt::If) -> String {
+        self.parenthesize("if", &[&stmt.condition])
+    }
+
+    fn visit_import(&mut self, stmt: &stmt::Import) -> String {
+        format!("(import {:?})", stmt.file)
+    }
+
+    fn visit_var(&mut self, stmt: &stmt::Var) -> String {
+        self.parenthesize(&format!("var {}", stmt.name.lexeme), &[&stmt.initializer])
+    }
+
+    fn visit_multi_var(&mut self, stmt: &stmt::MultiVar) -> String {
+        let vars: Vec<String> = stmt.vars.iter().map(|var| self.visit_var(var)).collect();
+        vars.join(" ")
+    }
+
+    fn visit_while(&mut self, stmt: &stmt::While) -> String {
+        match &stmt.label {
+            Some(label) => {
+                self.parenthesize(&format!("while {}:", label.lexeme), &[&stmt.condition])
+            }
+            None => self.parenthesize("while", &[&stmt.condition]),
+        }
+    }
+
+    fn visit_for_range(&mut self, stmt: &stmt::ForRange) -> String {
+        let mut bounds = vec![stmt.start.as_ref(), stmt.end.as_ref()];
+        bounds.extend(stmt.step.as_deref());
+        self.parenthesize(&format!("for {} ..", stmt.name.lexeme), &bounds)
+    }
+
+    fn visit_for_each(&mut self, stmt: &stmt::ForEach) -> String {
+        self.parenthesize(&format!("for {} in", stmt.name.lexeme), &[&stmt.iterable])
+    }
+
+    fn visit_function(&mut self, stmt: &stmt::Function) -> String {
+        let params: Vec<String> = stmt
+            .params
+            .iter()
+            .map(|(param, default)| match default {
+                Some(default) => format!("(= {} {})", param.lexeme, self.print_expr(default)),
+                None => param.lexeme.clone(),
+            })
+            .chain(stmt.rest.iter().map(|rest| format!("...{}", rest.lexeme)))
+            .collect();
+        format!("(fun {} ({}))", stmt.name.lexeme, params.join(" "))
+    }
+
+    fn visit_return(&mut self, stmt: &stmt::Return) -> String {
+        self.parenthesize("return", &[&stmt.value])
+    }
+
+    fn visit_try(&mut self, stmt: &stmt::Try) -> String {
+        format!("(try catch {})", stmt.name.lexeme)
+    }
+}
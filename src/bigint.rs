@@ -0,0 +1,201 @@
+use std::cmp::Ordering;
+
+use num_bigint::{BigInt, Sign};
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
+
+use crate::{
+    error_reporter::ErrorReporter,
+    interpreter::{Exit, comparison_error, operand_error},
+    token::{LiteralType, Token, TokenType},
+};
+
+/// `pow` refuses to compute bigints with more bits, they'd take long and a lot of memory
+const MAX_POW_BITS: u64 = 1 << 20;
+
+fn is_bigint(value: &LiteralType) -> bool {
+    matches!(value, LiteralType::BigInt(_))
+}
+
+/// The exact integer of a bigint or an integral number, `None` for everything else
+fn to_bigint(value: &LiteralType) -> Option<BigInt> {
+    match value {
+        LiteralType::BigInt(n) => Some(n.clone()),
+        LiteralType::Number(n) if n.fract() == 0.0 => BigInt::from_f64(*n),
+        _ => None,
+    }
+}
+
+/// Result of the binary `operator` if one of the operands is a bigint, `None` if neither is
+///
+/// The other operand is promoted to a bigint, which only works for integral numbers,
+/// except for comparisons which work with any number.
+pub fn binary(
+    operator: &Token,
+    left: &LiteralType,
+    right: &LiteralType,
+    reporter: &ErrorReporter,
+) -> Option<Result<LiteralType, Exit>> {
+    if !is_bigint(left) && !is_bigint(right) {
+        return None;
+    }
+
+    match operator.token_type {
+        TokenType::EqualEqual => return Some(Ok(LiteralType::Boolean(equals(left, right)))),
+        TokenType::BangEqual => return Some(Ok(LiteralType::Boolean(!equals(left, right)))),
+        TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual => {
+            return Some(comparison(operator, left, right, reporter));
+        }
+        _ => {}
+    }
+
+    let (Some(l_val), Some(r_val)) = (to_bigint(left), to_bigint(right)) else {
+        return Some(Err(operand_error(
+            operator,
+            "a bigint or an integral number",
+            &[left, right],
+            reporter,
+        )));
+    };
+    let divides = matches!(
+        operator.token_type,
+        TokenType::Slash | TokenType::Percentage | TokenType::Div
+    );
+    if divides && r_val.is_zero() {
+        reporter.error(operator.line, "Bigint division by zero");
+        return Some(Err(Exit::RuntimeError));
+    }
+
+    Some(Ok(LiteralType::BigInt(match operator.token_type {
+        TokenType::Plus => l_val + r_val,
+        TokenType::Minus => l_val - r_val,
+        TokenType::Star => l_val * r_val,
+        // truncates like integer division in most languages, `div` floors
+        TokenType::Slash => l_val / r_val,
+        TokenType::Percentage => l_val % r_val,
+        TokenType::Div => floor_div(&l_val, &r_val),
+        _ => unreachable!(),
+    })))
+}
+
+/// `pow(base, exponent)` if one of them is a bigint, `None` if neither is
+///
+/// Like for operators the other one is promoted to a bigint, the exponent can't be negative.
+pub fn pow(
+    base: &LiteralType,
+    exponent: &LiteralType,
+    paren: &Token,
+    reporter: &ErrorReporter,
+) -> Option<Result<LiteralType, Exit>> {
+    if !is_bigint(base) && !is_bigint(exponent) {
+        return None;
+    }
+
+    let (Some(base_val), Some(exponent_val)) = (to_bigint(base), to_bigint(exponent)) else {
+        reporter.error(
+            paren.line,
+            &format!(
+                "Arguments of pow must be bigints or integral numbers, got {}, {}",
+                base.to_debug_string(),
+                exponent.to_debug_string()
+            ),
+        );
+        return Some(Err(Exit::RuntimeError));
+    };
+    let Some(exponent_val) = exponent_val.to_u32() else {
+        reporter.error(
+            paren.line,
+            &format!(
+                "Bigint exponent must be a non-negative integer below 2^32, got {}.",
+                exponent.to_debug_string()
+            ),
+        );
+        return Some(Err(Exit::RuntimeError));
+    };
+    // the result has at least this many bits, e.g. 1 for a base of 1
+    let bits = (base_val.bits().saturating_sub(1)).saturating_mul(u64::from(exponent_val));
+    if bits > MAX_POW_BITS {
+        reporter.error(
+            paren.line,
+            &format!("Bigint result of pow would have more than {MAX_POW_BITS} bits."),
+        );
+        return Some(Err(Exit::RuntimeError));
+    }
+    Some(Ok(LiteralType::BigInt(base_val.pow(exponent_val))))
+}
+
+/// Whether a bigint and another value are the same number
+pub fn equals(left: &LiteralType, right: &LiteralType) -> bool {
+    match (to_bigint(left), to_bigint(right)) {
+        (Some(l_val), Some(r_val)) => l_val == r_val,
+        _ => false,
+    }
+}
+
+fn comparison(
+    operator: &Token,
+    left: &LiteralType,
+    right: &LiteralType,
+    reporter: &ErrorReporter,
+) -> Result<LiteralType, Exit> {
+    let ordering = match (left, right) {
+        (LiteralType::BigInt(l_val), LiteralType::BigInt(r_val)) => Some(l_val.cmp(r_val)),
+        (LiteralType::BigInt(l_val), LiteralType::Number(r_val)) => {
+            compare_to_number(l_val, *r_val)
+        }
+        (LiteralType::Number(l_val), LiteralType::BigInt(r_val)) => {
+            compare_to_number(r_val, *l_val).map(Ordering::reverse)
+        }
+        _ => {
+            return Err(comparison_error(
+                operator.line,
+                &operator.lexeme,
+                left,
+                right,
+                reporter,
+            ));
+        }
+    };
+
+    // like for numbers every comparison with NaN is false
+    Ok(LiteralType::Boolean(ordering.is_some_and(
+        |ordering| match operator.token_type {
+            TokenType::Greater => ordering.is_gt(),
+            TokenType::GreaterEqual => ordering.is_ge(),
+            TokenType::Less => ordering.is_lt(),
+            _ => ordering.is_le(),
+        },
+    )))
+}
+
+/// Exact ordering of a bigint and a number, `None` if the number is NaN
+fn compare_to_number(bigint: &BigInt, number: f64) -> Option<Ordering> {
+    if number.is_nan() {
+        None
+    } else if number.is_infinite() {
+        Some(if number > 0.0 {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        })
+    } else if number.fract() == 0.0 {
+        Some(bigint.cmp(&BigInt::from_f64(number)?))
+    } else {
+        // the number lies strictly between its floor and the next integer
+        let floor = BigInt::from_f64(number.floor())?;
+        Some(if *bigint <= floor {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        })
+    }
+}
+
+/// Division rounding towards negative infinity like `div` does for numbers
+fn floor_div(l_val: &BigInt, r_val: &BigInt) -> BigInt {
+    let quotient = l_val / r_val;
+    if (l_val % r_val).is_zero() || l_val.sign() == r_val.sign() || l_val.sign() == Sign::NoSign {
+        quotient
+    } else {
+        quotient - 1
+    }
+}
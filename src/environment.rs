@@ -1,15 +1,17 @@
 use crate::{
+    error_reporter::ErrorReporter,
     interpreter::Exit,
-    report,
     token::{LiteralType, Token},
 };
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub struct Environment {
     pub values: HashMap<String, LiteralType>,
+    /// Names which are declared in this scope but whose declaration hasn't been executed yet
+    pub uninitialized: HashSet<String>,
     pub enclosing: Option<Rc<RefCell<Environment>>>,
 }
 
@@ -17,6 +19,7 @@ impl Environment {
     pub fn new() -> Self {
         Self {
             values: HashMap::new(),
+            uninitialized: HashSet::new(),
             enclosing: None,
         }
     }
@@ -24,57 +27,88 @@ impl Environment {
     pub fn new_with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
         Environment {
             values: HashMap::new(),
+            uninitialized: HashSet::new(),
             enclosing: Some(enclosing),
         }
     }
 
+    /// Marks `name` as declared without giving it a value yet
+    pub fn declare(&mut self, name: String) {
+        if !self.values.contains_key(&name) {
+            self.uninitialized.insert(name);
+        }
+    }
+
     pub fn define(&mut self, name: String, value: LiteralType) {
+        self.uninitialized.remove(&name);
         self.values.insert(name, value);
     }
 
-    pub fn assign(&mut self, name: &Token, value: LiteralType) -> Result<(), Exit> {
+    pub fn assign(
+        &mut self,
+        name: &Token,
+        value: LiteralType,
+        reporter: &ErrorReporter,
+    ) -> Result<(), Exit> {
         #[allow(clippy::map_entry)]
         if self.values.contains_key(&name.lexeme) {
             self.values.insert(name.lexeme.clone(), value);
             Ok(())
+        } else if self.uninitialized.contains(&name.lexeme) {
+            self.define(name.lexeme.clone(), value);
+            Ok(())
         } else {
             if let Some(enclosing) = &self.enclosing {
-                return enclosing.borrow_mut().assign(name, value);
+                return enclosing.borrow_mut().assign(name, value, reporter);
             }
-            report(
+            reporter.error(
                 name.line,
-                "",
                 &format!("Assigning to undefinied variable {}.", name.lexeme),
             );
             Err(Exit::RuntimeError)
         }
     }
 
-    pub fn get(&self, name: &Token) -> Result<LiteralType, Exit> {
+    pub fn get(&self, name: &Token, reporter: &ErrorReporter) -> Result<LiteralType, Exit> {
         if self.values.contains_key(&name.lexeme) {
             Ok(self.values.get(&name.lexeme).unwrap().clone())
+        } else if self.uninitialized.contains(&name.lexeme) {
+            reporter.error(
+                name.line,
+                &format!("Variable used before initialization: {}.", name.lexeme),
+            );
+            Err(Exit::RuntimeError)
         } else {
             if let Some(enclosing) = &self.enclosing {
-                return enclosing.borrow_mut().get(name);
+                return enclosing.borrow_mut().get(name, reporter);
             }
-            report(
-                name.line,
-                "",
-                &format!("Undefinied variable {}.", name.lexeme),
-            );
+            reporter.error(name.line, &format!("Undefinied variable {}.", name.lexeme));
             Err(Exit::RuntimeError)
         }
     }
 
-    pub fn get_at(&self, distance: usize, name: &Token) -> Result<LiteralType, Exit> {
+    pub fn get_at(
+        &self,
+        distance: usize,
+        name: &Token,
+        reporter: &ErrorReporter,
+    ) -> Result<LiteralType, Exit> {
         if distance == 0 {
-            self.get(name)
+            // the resolver found the declaration in this scope, it just didn't run yet, e.g.
+            // in a function called before the `var` it reads
+            self.values.get(&name.lexeme).cloned().ok_or_else(|| {
+                reporter.error(
+                    name.line,
+                    &format!("Variable used before initialization: {}.", name.lexeme),
+                );
+                Exit::RuntimeError
+            })
         } else {
             self.enclosing
                 .as_ref()
                 .unwrap()
                 .borrow()
-                .get_at(distance - 1, name)
+                .get_at(distance - 1, name, reporter)
         }
     }
 
@@ -0,0 +1,189 @@
+use std::{cell::RefCell, fmt::Display, rc::Rc};
+
+use crate::scanner::LineDirective;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// An error or warning reported by one of the phases running a script
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// Line as printed in messages, so after applying `#line` directives
+    pub line: usize,
+    /// 1-based, only known for errors about a single token or character
+    pub column: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// `{"line": 1, "column": 5, "severity": "error", "message": "..."}`, the column can be `null`
+    pub fn to_json(&self) -> String {
+        let column = match self.column {
+            Some(column) => column.to_string(),
+            None => "null".to_owned(),
+        };
+        format!(
+            "{{\"line\": {}, \"column\": {column}, \"severity\": \"{}\", \"message\": {}}}",
+            self.line,
+            self.severity,
+            json_string(&self.message)
+        )
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+        };
+        write!(f, "[line {}] {severity}: {}", self.line, self.message)
+    }
+}
+
+/// `diagnostics` as a JSON array with one object per line, see [`Diagnostic::to_json`]
+pub fn diagnostics_to_json(diagnostics: &[Diagnostic]) -> String {
+    if diagnostics.is_empty() {
+        return "[]".to_owned();
+    }
+    let objects: Vec<String> = diagnostics
+        .iter()
+        .map(|diagnostic| format!("  {}", diagnostic.to_json()))
+        .collect();
+    format!("[\n{}\n]", objects.join(",\n"))
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\t' => json.push_str("\\t"),
+            '\r' => json.push_str("\\r"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Where the scanner, parser, resolver and interpreter send their errors and warnings
+///
+/// Clones share the same diagnostics, so each phase holds one. The default one prints
+/// every diagnostic to stderr when it's reported, a quiet one only keeps them.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorReporter {
+    state: Rc<RefCell<ReporterState>>,
+}
+
+#[derive(Debug, Default)]
+struct ReporterState {
+    diagnostics: Vec<Diagnostic>,
+    quiet: bool,
+    /// `#line` directives of the script being run, used to translate lines in messages
+    line_directives: Vec<LineDirective>,
+}
+
+impl ErrorReporter {
+    /// A reporter keeping the diagnostics without printing them, e.g. to print them as JSON
+    pub fn quiet() -> Self {
+        let reporter = Self::default();
+        reporter.state.borrow_mut().quiet = true;
+        reporter
+    }
+
+    pub fn error(&self, line: usize, message: &str) {
+        self.error_at(line, None, message);
+    }
+
+    /// Like `error`, for errors about the token or character at `column`
+    pub fn error_at(&self, line: usize, column: Option<usize>, message: &str) {
+        if self.collect(line, column, Severity::Error, message) {
+            eprintln!("[{}] Error : {}", self.source_location(line), message);
+        }
+    }
+
+    pub fn warn(&self, line: usize, message: &str) {
+        if self.collect(line, None, Severity::Warning, message) {
+            eprintln!("[{}] Warning: {}", self.source_location(line), message);
+        }
+    }
+
+    /// Everything reported so far
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.state.borrow().diagnostics.clone()
+    }
+
+    pub fn had_error(&self) -> bool {
+        self.state
+            .borrow()
+            .diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// Forgets the diagnostics reported so far, e.g. before running the next line of the prompt
+    pub fn clear(&self) {
+        self.state.borrow_mut().diagnostics.clear();
+    }
+
+    pub(crate) fn set_line_directives(&self, line_directives: Vec<LineDirective>) {
+        self.state.borrow_mut().line_directives = line_directives;
+    }
+
+    /// Keeps the diagnostic, returns whether it should be printed too
+    fn collect(
+        &self,
+        line: usize,
+        column: Option<usize>,
+        severity: Severity,
+        message: &str,
+    ) -> bool {
+        let line = self.translate_line(line).1;
+        let mut state = self.state.borrow_mut();
+        state.diagnostics.push(Diagnostic {
+            line,
+            column,
+            severity,
+            message: message.trim().to_owned(),
+        });
+        !state.quiet
+    }
+
+    /// `line N`, or `file line N` after a `#line N "file"` directive
+    pub(crate) fn source_location(&self, line: usize) -> String {
+        match self.translate_line(line) {
+            (Some(file), line) => format!("{file} line {line}"),
+            (None, line) => format!("line {line}"),
+        }
+    }
+
+    /// File and line that `line` of the script stands for according to its `#line` directives
+    fn translate_line(&self, line: usize) -> (Option<String>, usize) {
+        let state = self.state.borrow();
+        match state.line_directives.iter().rev().find(|d| d.line <= line) {
+            Some(LineDirective {
+                line: start,
+                reported_line,
+                file,
+            }) => (file.clone(), reported_line + (line - start)),
+            None => (None, line),
+        }
+    }
+}
@@ -2,22 +2,31 @@ use crate::token::{LiteralType, Token};
 use std::hash::Hash;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Expr {
     Assignment(Assignment),
     Binary(Binary),
     Call(Call),
     Get(Get),
     Grouping(Grouping),
+    Index(Index),
+    Interpolation(Interpolation),
+    List(List),
     Literal(Literal),
     Logical(Logical),
+    OptionalChain(OptionalChain),
     Set(Set),
+    SetIndex(SetIndex),
+    Slice(Slice),
     Unary(Unary),
+    Unwrap(Unwrap),
     SelfExpr(SelfExpr),
     SuperExpr(SuperExpr),
     Variable(Variable),
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Assignment {
     pub name: Token,
     pub value: Box<Expr>,
@@ -25,12 +34,14 @@ pub struct Assignment {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Variable {
     pub name: Token,
     pub uuid: usize,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Binary {
     pub left: Box<Expr>,
     pub operator: Token,
@@ -39,6 +50,7 @@ pub struct Binary {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Call {
     pub callee: Box<Expr>,
     pub paren: Token,
@@ -47,25 +59,56 @@ pub struct Call {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Get {
     pub object: Box<Expr>,
     pub name: Token,
+    /// Accessed with `?.`, evaluates to nil instead of failing
+    pub optional: bool,
     pub uuid: usize,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Grouping {
     pub expr: Box<Expr>,
     pub uuid: usize,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Index {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+    pub uuid: usize,
+}
+
+/// `"a ${b} c"`, the string fragments are string literals and the values of the other parts
+/// are converted to strings like `print` does
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Interpolation {
+    pub parts: Vec<Expr>,
+    pub uuid: usize,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct List {
+    pub elements: Vec<Expr>,
+    pub uuid: usize,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Literal {
     pub value: LiteralType,
     pub uuid: usize,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Logical {
     pub left: Box<Expr>,
     pub operator: Token,
@@ -73,6 +116,7 @@ pub struct Logical {
     pub uuid: usize,
 }
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Set {
     pub object: Box<Expr>,
     pub name: Token,
@@ -80,6 +124,27 @@ pub struct Set {
     pub uuid: usize,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SetIndex {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+    pub value: Box<Expr>,
+    pub uuid: usize,
+}
+
+/// `object[start:end]`, either bound can be left out
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Slice {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub start: Option<Box<Expr>>,
+    pub end: Option<Box<Expr>>,
+    pub uuid: usize,
+}
+
 impl Literal {
     pub fn new(value: LiteralType, uuid: usize) -> Self {
         Self { value, uuid }
@@ -87,19 +152,40 @@ impl Literal {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Unary {
     pub operator: Token,
     pub right: Box<Expr>,
     pub uuid: usize,
 }
 
+/// Calls, property accesses and indexing following a `?.` like `a?.b.c()`, the whole chain
+/// is `nil` when the object of a `?.` is
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OptionalChain {
+    pub expr: Box<Expr>,
+    pub uuid: usize,
+}
+
+/// `value!`, which is `value` unless it's `nil`, then it's a runtime error
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Unwrap {
+    pub expr: Box<Expr>,
+    pub bang: Token,
+    pub uuid: usize,
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SelfExpr {
     pub keyword: Token,
     pub uuid: usize,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SuperExpr {
     pub keyword: Token,
     pub method: Token,
@@ -112,10 +198,17 @@ pub trait Visitor<T> {
     fn visit_call(&mut self, expr: &Call) -> T;
     fn visit_get(&mut self, expr: &Get) -> T;
     fn visit_grouping(&mut self, expr: &Grouping) -> T;
+    fn visit_index(&mut self, expr: &Index) -> T;
+    fn visit_interpolation(&mut self, expr: &Interpolation) -> T;
+    fn visit_list(&mut self, expr: &List) -> T;
     fn visit_literal(&self, expr: &Literal) -> T;
     fn visit_logical(&mut self, expr: &Logical) -> T;
+    fn visit_optional_chain(&mut self, expr: &OptionalChain) -> T;
     fn visit_unary(&mut self, expr: &Unary) -> T;
+    fn visit_unwrap(&mut self, expr: &Unwrap) -> T;
     fn visit_set(&mut self, expr: &Set) -> T;
+    fn visit_set_index(&mut self, expr: &SetIndex) -> T;
+    fn visit_slice(&mut self, expr: &Slice) -> T;
     fn visit_self_expr(&mut self, expr: &SelfExpr) -> T;
     fn visit_super_expr(&mut self, expr: &SuperExpr) -> T;
     fn visit_variable(&mut self, expr: &Variable) -> T;
@@ -129,10 +222,17 @@ impl Expr {
             Expr::Call(call) => visitor.visit_call(call),
             Expr::Get(get) => visitor.visit_get(get),
             Expr::Grouping(grouping) => visitor.visit_grouping(grouping),
+            Expr::Index(index) => visitor.visit_index(index),
+            Expr::Interpolation(interpolation) => visitor.visit_interpolation(interpolation),
+            Expr::List(list) => visitor.visit_list(list),
             Expr::Literal(literal) => visitor.visit_literal(literal),
             Expr::Logical(logical) => visitor.visit_logical(logical),
+            Expr::OptionalChain(chain) => visitor.visit_optional_chain(chain),
             Expr::Unary(unary) => visitor.visit_unary(unary),
+            Expr::Unwrap(unwrap) => visitor.visit_unwrap(unwrap),
             Expr::Set(set) => visitor.visit_set(set),
+            Expr::SetIndex(set_index) => visitor.visit_set_index(set_index),
+            Expr::Slice(slice) => visitor.visit_slice(slice),
             Expr::SelfExpr(self_expr) => visitor.visit_self_expr(self_expr),
             Expr::SuperExpr(super_expr) => visitor.visit_super_expr(super_expr),
             Expr::Variable(variable) => visitor.visit_variable(variable),
@@ -146,10 +246,17 @@ impl Expr {
             Expr::Call(e) => e.uuid,
             Expr::Get(e) => e.uuid,
             Expr::Grouping(e) => e.uuid,
+            Expr::Index(e) => e.uuid,
+            Expr::Interpolation(e) => e.uuid,
+            Expr::List(e) => e.uuid,
             Expr::Literal(e) => e.uuid,
             Expr::Logical(e) => e.uuid,
+            Expr::OptionalChain(e) => e.uuid,
             Expr::Unary(e) => e.uuid,
+            Expr::Unwrap(e) => e.uuid,
             Expr::Set(e) => e.uuid,
+            Expr::SetIndex(e) => e.uuid,
+            Expr::Slice(e) => e.uuid,
             Expr::SelfExpr(e) => e.uuid,
             Expr::SuperExpr(e) => e.uuid,
             Expr::Variable(e) => e.uuid,
@@ -0,0 +1,411 @@
+use crate::{
+    expr::{self, Expr},
+    stmt::{self, Stmt},
+    token::LiteralType,
+};
+
+const INDENT: &str = "    ";
+
+/// Turns the AST back into Lox source with consistent indentation and spacing
+///
+/// Comments aren't part of the AST so they get lost, and `for` loops come out
+/// as the `while` loops they're desugared to.
+#[derive(Default)]
+pub struct SourceFormatter {
+    depth: usize,
+}
+
+impl SourceFormatter {
+    pub fn format(&mut self, statements: &[Stmt]) -> String {
+        let mut source = String::new();
+        for stmt in statements {
+            source.push_str(&self.format_stmt(stmt));
+            source.push('\n');
+        }
+        source
+    }
+
+    fn format_stmt(&mut self, stmt: &Stmt) -> String {
+        stmt.accept(self)
+    }
+
+    fn format_expr(&mut self, expr: &Expr) -> String {
+        expr.accept(self)
+    }
+
+    fn format_exprs(&mut self, exprs: &[Expr]) -> String {
+        let formatted: Vec<String> = exprs.iter().map(|expr| self.format_expr(expr)).collect();
+        formatted.join(", ")
+    }
+
+    /// `{`, the statements one per line indented one level deeper and `}`
+    fn block(&mut self, statements: &[Stmt]) -> String {
+        if statements.is_empty() {
+            return String::from("{}");
+        }
+        self.depth += 1;
+        let mut block = String::from("{\n");
+        for stmt in statements {
+            block.push_str(&INDENT.repeat(self.depth));
+            block.push_str(&self.format_stmt(stmt));
+            block.push('\n');
+        }
+        self.depth -= 1;
+        block.push_str(&INDENT.repeat(self.depth));
+        block.push('}');
+        block
+    }
+
+    /// Body of `if`, `else` or `while`, blocks stay on the line, anything else
+    /// goes on its own line indented one level deeper
+    fn branch(&mut self, stmt: &Stmt) -> String {
+        if let Stmt::Block(block) = stmt {
+            format!(" {}", self.block(&block.statements))
+        } else {
+            self.depth += 1;
+            let branch = format!("\n{}{}", INDENT.repeat(self.depth), self.format_stmt(stmt));
+            self.depth -= 1;
+            branch
+        }
+    }
+
+    /// `name = initializer` of a variable declaration, just `name` if it's `nil`
+    fn binding(&mut self, var: &stmt::Var) -> String {
+        match var.initializer.as_ref() {
+            Expr::Literal(expr::Literal {
+                value: LiteralType::Nil,
+                ..
+            }) => var.name.lexeme.clone(),
+            initializer => format!("{} = {}", var.name.lexeme, self.format_expr(initializer)),
+        }
+    }
+
+    /// Parameters, name and body without `fun`, which methods don't have
+    fn function(&mut self, function: &stmt::Function) -> String {
+        let mut params: Vec<String> = Vec::new();
+        for (param, default) in &function.params {
+            match default {
+                Some(default) => {
+                    params.push(format!("{} = {}", param.lexeme, self.format_expr(default)))
+                }
+                None => params.push(param.lexeme.clone()),
+            }
+        }
+        if let Some(rest) = &function.rest {
+            params.push(format!("...{}", rest.lexeme));
+        }
+        format!(
+            "{}({}) {}",
+            function.name.lexeme,
+            params.join(", "),
+            self.block(&function.body)
+        )
+    }
+}
+
+/// Quotes `s` escaping everything the scanner would read differently
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // would start an interpolation
+            '$' if chars.peek() == Some(&'{') => quoted.push_str("\\$"),
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            '\0' => quoted.push_str("\\0"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl expr::Visitor<String> for SourceFormatter {
+    fn visit_assignment(&mut self, expr: &expr::Assignment) -> String {
+        format!("{} = {}", expr.name.lexeme, self.format_expr(&expr.value))
+    }
+
+    fn visit_binary(&mut self, expr: &expr::Binary) -> String {
+        format!(
+            "{} {} {}",
+            self.format_expr(&expr.left),
+            expr.operator.lexeme,
+            self.format_expr(&expr.right)
+        )
+    }
+
+    fn visit_call(&mut self, expr: &expr::Call) -> String {
+        format!(
+            "{}({})",
+            self.format_expr(&expr.callee),
+            self.format_exprs(&expr.arguments)
+        )
+    }
+
+    fn visit_get(&mut self, expr: &expr::Get) -> String {
+        let operator = if expr.optional { "?." } else { "." };
+        format!(
+            "{}{operator}{}",
+            self.format_expr(&expr.object),
+            expr.name.lexeme
+        )
+    }
+
+    fn visit_grouping(&mut self, expr: &expr::Grouping) -> String {
+        format!("({})", self.format_expr(&expr.expr))
+    }
+
+    fn visit_index(&mut self, expr: &expr::Index) -> String {
+        format!(
+            "{}[{}]",
+            self.format_expr(&expr.object),
+            self.format_expr(&expr.index)
+        )
+    }
+
+    fn visit_interpolation(&mut self, expr: &expr::Interpolation) -> String {
+        let mut formatted = String::from("\"");
+        for part in &expr.parts {
+            match part {
+                Expr::Literal(expr::Literal {
+                    value: LiteralType::String(s),
+                    ..
+                }) => {
+                    let quoted = quote(s);
+                    formatted.push_str(&quoted[1..quoted.len() - 1]);
+                }
+                part => formatted.push_str(&format!("${{{}}}", self.format_expr(part))),
+            }
+        }
+        formatted.push('"');
+        formatted
+    }
+
+    fn visit_list(&mut self, expr: &expr::List) -> String {
+        format!("[{}]", self.format_exprs(&expr.elements))
+    }
+
+    fn visit_literal(&self, expr: &expr::Literal) -> String {
+        match &expr.value {
+            LiteralType::String(s) => quote(s),
+            // the scanner doesn't know scientific notation
+            LiteralType::Number(n) => n.to_string(),
+            #[cfg(feature = "bigint")]
+            LiteralType::BigInt(n) => format!("{n}n"),
+            value => value.to_string(),
+        }
+    }
+
+    fn visit_logical(&mut self, expr: &expr::Logical) -> String {
+        format!(
+            "{} {} {}",
+            self.format_expr(&expr.left),
+            expr.operator.lexeme,
+            self.format_expr(&expr.right)
+        )
+    }
+
+    fn visit_unary(&mut self, expr: &expr::Unary) -> String {
+        format!("{}{}", expr.operator.lexeme, self.format_expr(&expr.right))
+    }
+
+    fn visit_optional_chain(&mut self, expr: &expr::OptionalChain) -> String {
+        self.format_expr(&expr.expr)
+    }
+
+    fn visit_unwrap(&mut self, expr: &expr::Unwrap) -> String {
+        format!("{}!", self.format_expr(&expr.expr))
+    }
+
+    fn visit_set(&mut self, expr: &expr::Set) -> String {
+        format!(
+            "{}.{} = {}",
+            self.format_expr(&expr.object),
+            expr.name.lexeme,
+            self.format_expr(&expr.value)
+        )
+    }
+
+    fn visit_set_index(&mut self, expr: &expr::SetIndex) -> String {
+        format!(
+            "{}[{}] = {}",
+            self.format_expr(&expr.object),
+            self.format_expr(&expr.index),
+            self.format_expr(&expr.value)
+        )
+    }
+
+    fn visit_slice(&mut self, expr: &expr::Slice) -> String {
+        let object = self.format_expr(&expr.object);
+        let start = expr.start.as_ref().map(|start| self.format_expr(start));
+        let end = expr.end.as_ref().map(|end| self.format_expr(end));
+        format!(
+            "{object}[{}:{}]",
+            start.unwrap_or_default(),
+            end.unwrap_or_default()
+        )
+    }
+
+    fn visit_self_expr(&mut self, _: &expr::SelfExpr) -> String {
+        String::from("self")
+    }
+
+    fn visit_super_expr(&mut self, expr: &expr::SuperExpr) -> String {
+        format!("super.{}", expr.method.lexeme)
+    }
+
+    fn visit_variable(&mut self, expr: &expr::Variable) -> String {
+        expr.name.lexeme.clone()
+    }
+}
+
+impl stmt::Visitor<String> for SourceFormatter {
+    fn visit_block(&mut self, stmt: &stmt::Block) -> String {
+        self.block(&stmt.statements)
+    }
+
+    fn visit_break(&mut self, stmt: &stmt::Break) -> String {
+        match &stmt.label {
+            Some(label) => format!("break {};", label.lexeme),
+            None => String::from("break;"),
+        }
+    }
+
+    fn visit_class(&mut self, stmt: &stmt::Class) -> String {
+        let mut class = format!("class {}", stmt.name.lexeme);
+        if let Some(superclass) = &stmt.superclass {
+            class.push_str(&format!(" < {}", self.format_expr(superclass)));
+        }
+        if stmt.methods.is_empty() {
+            class.push_str(" {}");
+            return class;
+        }
+
+        self.depth += 1;
+        class.push_str(" {\n");
+        for method in &stmt.methods {
+            if let Stmt::Function(method) = method {
+                class.push_str(&INDENT.repeat(self.depth));
+                class.push_str(&self.function(method));
+                class.push('\n');
+            }
+        }
+        self.depth -= 1;
+        class.push_str(&INDENT.repeat(self.depth));
+        class.push('}');
+        class
+    }
+
+    fn visit_expression(&mut self, stmt: &stmt::Expression) -> String {
+        format!("{};", self.format_expr(&stmt.expr))
+    }
+
+    fn visit_if(&mut self, stmt: &stmt::If) -> String {
+        let mut formatted = format!(
+            "if ({}){}",
+            self.format_expr(&stmt.condition),
+            self.branch(&stmt.then_branch)
+        );
+        if let Some(else_branch) = &stmt.else_branch {
+            if !matches!(stmt.then_branch.as_ref(), Stmt::Block(_)) {
+                formatted.push('\n');
+                formatted.push_str(&INDENT.repeat(self.depth));
+            } else {
+                formatted.push(' ');
+            }
+            formatted.push_str("else");
+            if let Stmt::If(_) = else_branch.as_ref() {
+                formatted.push(' ');
+                formatted.push_str(&self.format_stmt(else_branch));
+            } else {
+                formatted.push_str(&self.branch(else_branch));
+            }
+        }
+        formatted
+    }
+
+    fn visit_import(&mut self, stmt: &stmt::Import) -> String {
+        format!("import {};", quote(&stmt.file))
+    }
+
+    fn visit_var(&mut self, stmt: &stmt::Var) -> String {
+        format!("var {};", self.binding(stmt))
+    }
+
+    fn visit_multi_var(&mut self, stmt: &stmt::MultiVar) -> String {
+        let bindings: Vec<String> = stmt.vars.iter().map(|var| self.binding(var)).collect();
+        format!("var {};", bindings.join(", "))
+    }
+
+    fn visit_while(&mut self, stmt: &stmt::While) -> String {
+        let label = match &stmt.label {
+            Some(label) => format!("{}: ", label.lexeme),
+            None => String::new(),
+        };
+        format!(
+            "{label}while ({}){}",
+            self.format_expr(&stmt.condition),
+            self.branch(&stmt.body)
+        )
+    }
+
+    fn visit_for_range(&mut self, stmt: &stmt::ForRange) -> String {
+        let label = match &stmt.label {
+            Some(label) => format!("{}: ", label.lexeme),
+            None => String::new(),
+        };
+        let mut range = format!(
+            "{}..{}",
+            self.format_expr(&stmt.start),
+            self.format_expr(&stmt.end)
+        );
+        if let Some(step) = &stmt.step {
+            range.push_str(&format!("..{}", self.format_expr(step)));
+        }
+        format!(
+            "{label}for ({} in {range}){}",
+            stmt.name.lexeme,
+            self.branch(&stmt.body)
+        )
+    }
+
+    fn visit_for_each(&mut self, stmt: &stmt::ForEach) -> String {
+        let label = match &stmt.label {
+            Some(label) => format!("{}: ", label.lexeme),
+            None => String::new(),
+        };
+        format!(
+            "{label}for ({} in {}){}",
+            stmt.name.lexeme,
+            self.format_expr(&stmt.iterable),
+            self.branch(&stmt.body)
+        )
+    }
+
+    fn visit_function(&mut self, stmt: &stmt::Function) -> String {
+        format!("fun {}", self.function(stmt))
+    }
+
+    fn visit_return(&mut self, stmt: &stmt::Return) -> String {
+        match stmt.value.as_ref() {
+            Expr::Literal(expr::Literal {
+                value: LiteralType::Nil,
+                ..
+            }) => String::from("return;"),
+            value => format!("return {};", self.format_expr(value)),
+        }
+    }
+
+    fn visit_try(&mut self, stmt: &stmt::Try) -> String {
+        format!(
+            "try {} catch ({}) {}",
+            self.block(&stmt.body),
+            stmt.name.lexeme,
+            self.block(&stmt.handler)
+        )
+    }
+}
@@ -1,86 +1,233 @@
 use crate::{
+    ast_printer::AstPrinter,
     environment::Environment,
+    error_reporter::ErrorReporter,
     expr::{self, Binary, Expr, Grouping, Literal, Logical, Unary},
-    lox_callable::{Callable, LoxCallable, LoxClass, LoxFunction, NativeFunction},
-    report,
+    lox_callable::{
+        Callable, LoxCallable, LoxClass, LoxFunction, LoxInstance, NativeFunction, is_initializer,
+    },
+    stdlib,
     stmt::{self, Expression, Stmt},
     token::{LiteralType, Token, TokenType},
 };
 
-use std::{
-    cell::RefCell,
-    collections::HashMap,
-    rc::Rc,
-    time::{SystemTime, UNIX_EPOCH},
-};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+/// Repeating a string with `*` refuses to build longer ones (in bytes), they'd exhaust memory
+const MAX_STRING_LEN: usize = 1 << 30;
 
 #[derive(Debug)]
 pub enum Exit {
     RuntimeError,
     Return(LiteralType),
+    /// Leaves the loop with that label or the innermost one
+    Break(Option<String>),
+    /// A value thrown with `throw()` which wasn't caught yet
+    Throw(LiteralType),
+    /// `?.` found `nil`, which makes the rest of its [`expr::OptionalChain`] `nil` too
+    ShortCircuit,
 }
 
 pub struct Interpreter {
     pub environment: Rc<RefCell<Environment>>,
     pub globals: Rc<RefCell<Environment>>,
-    locals: HashMap<Expr, usize>,
+    /// Scope distances of resolved local variables by the uuid of the expression using them
+    locals: HashMap<usize, usize>,
+    /// Whether `return` is allowed outside of functions to end the script
+    pub top_level_return: bool,
+    /// Print each statement before executing it and each evaluated expression to stderr
+    pub trace: bool,
+    /// Treat `0`, `""` and empty lists as falsy too, like Python does
+    pub empty_is_falsy: bool,
+    /// Name and call line of the functions currently running, innermost last
+    ///
+    /// Frames are left on the stack when a runtime error unwinds through them,
+    /// so `interpret` can print where it happened.
+    pub call_stack: Vec<(String, usize)>,
+    /// Where all phases running on this interpreter report their errors and warnings
+    pub reporter: ErrorReporter,
+    /// Loop iterations and function calls a run may take before it's aborted, unlimited if `None`
+    pub execution_limit: Option<usize>,
+    /// Loop iterations and function calls taken by the current run
+    steps: usize,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Configures which native functions an [`Interpreter`] gets, e.g. to sandbox untrusted scripts
+#[derive(Default)]
+pub struct InterpreterBuilder {
+    without_stdlib: bool,
+    excluded: Vec<String>,
+    natives: Vec<(String, NativeFunction)>,
+    top_level_return: bool,
+    empty_is_falsy: bool,
+    sandboxed: bool,
+    reporter: Option<ErrorReporter>,
+    execution_limit: Option<usize>,
+    script_args: Option<Vec<String>>,
+}
+
+impl InterpreterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Don't define any of the std functions
+    pub fn without_stdlib(mut self) -> Self {
+        self.without_stdlib = true;
+        self
+    }
+
+    /// Don't define the std function `name`
+    pub fn without_native(mut self, name: &str) -> Self {
+        self.excluded.push(name.to_string());
+        self
+    }
+
+    /// Defines an additional native function, this also works together with `without_stdlib`
+    pub fn with_native(mut self, name: &str, native: NativeFunction) -> Self {
+        self.natives.push((name.to_string(), native));
+        self
+    }
+
+    /// Allow `return value;` outside of functions, which ends the script and
+    /// makes [`Interpreter::interpret`] return that value
+    pub fn allow_top_level_return(mut self) -> Self {
+        self.top_level_return = true;
+        self
+    }
+
+    /// Don't define natives with access to the file system or environment like `readFile`
+    pub fn sandboxed(mut self) -> Self {
+        self.sandboxed = true;
+        self
+    }
+
+    /// Make `0`, `""` and empty lists falsy instead of only `false` and `nil`
+    pub fn empty_is_falsy(mut self) -> Self {
+        self.empty_is_falsy = true;
+        self
+    }
+
+    /// Abort runs with a runtime error once they took `max` loop iterations and function calls
+    /// together, so untrusted scripts can't hang the host with an infinite loop
+    pub fn execution_limit(mut self, max: usize) -> Self {
+        self.execution_limit = Some(max);
+        self
+    }
+
+    /// Define the global list `args` with the command-line arguments given to the script
+    pub fn script_args(mut self, args: Vec<String>) -> Self {
+        self.script_args = Some(args);
+        self
+    }
+
+    /// Report errors and warnings to `reporter` instead of printing them to stderr
+    pub fn error_reporter(mut self, reporter: ErrorReporter) -> Self {
+        self.reporter = Some(reporter);
+        self
+    }
+
+    pub fn build(self) -> Interpreter {
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        if !self.without_stdlib {
+            stdlib::define_globals(&mut globals.borrow_mut());
+            if !self.sandboxed {
+                stdlib::define_file_functions(&mut globals.borrow_mut());
+            }
+        }
+        for name in self.excluded {
+            globals.borrow_mut().values.remove(&name);
+        }
+        if let Some(args) = self.script_args {
+            let args = args
+                .into_iter()
+                .map(|arg| LiteralType::String(arg.into()))
+                .collect();
+            globals.borrow_mut().define(
+                "args".to_owned(),
+                LiteralType::List(Rc::new(RefCell::new(args))),
+            );
+        }
+        for (name, native) in self.natives {
+            globals
+                .borrow_mut()
+                .define(name, LiteralType::NativeFunction(native));
+        }
+
+        Interpreter {
+            environment: globals.clone(),
+            globals,
+            locals: HashMap::new(),
+            top_level_return: self.top_level_return,
+            trace: false,
+            empty_is_falsy: self.empty_is_falsy,
+            call_stack: Vec::new(),
+            reporter: self.reporter.unwrap_or_default(),
+            execution_limit: self.execution_limit,
+            steps: 0,
+        }
+    }
 }
 
 impl expr::Visitor<Result<LiteralType, Exit>> for Interpreter {
     fn visit_binary(&mut self, expr: &Binary) -> Result<LiteralType, Exit> {
         let left = self.evaluate(&expr.left)?;
         let right = self.evaluate(&expr.right)?;
+        if let Some(result) = self.call_operator_method(&expr.operator, &left, &right) {
+            return result;
+        }
+        #[cfg(feature = "bigint")]
+        if let Some(result) = crate::bigint::binary(&expr.operator, &left, &right, &self.reporter) {
+            return result;
+        }
         match expr.operator.token_type {
-            TokenType::Minus => {
-                if let LiteralType::Number(l_val) = left
-                    && let LiteralType::Number(r_val) = right
-                {
-                    Ok(LiteralType::Number(l_val - r_val))
-                } else {
-                    Err(Exit::RuntimeError)
-                }
-            }
-            TokenType::Slash => {
-                if let LiteralType::Number(l_val) = left
-                    && let LiteralType::Number(r_val) = right
-                {
-                    Ok(LiteralType::Number(l_val / r_val))
-                } else {
-                    report(
-                        expr.operator.line,
-                        "",
-                        &format!("Both operands of '/' must be a number, got {left}, {right}"),
-                    );
-                    Err(Exit::RuntimeError)
+            // "ab" * 3 is "ababab"
+            TokenType::Star if let LiteralType::String(s) = &left => match right {
+                LiteralType::Number(count) if count >= 0.0 && count.fract() == 0.0 => {
+                    match s.len().checked_mul(count as usize) {
+                        Some(len) if len <= MAX_STRING_LEN => {
+                            Ok(LiteralType::String(s.repeat(count as usize).into()))
+                        }
+                        _ => {
+                            self.reporter.error(expr.operator.line, "String too long.");
+                            Err(Exit::RuntimeError)
+                        }
+                    }
                 }
-            }
-            TokenType::Percentage => {
-                if let LiteralType::Number(l_val) = left
-                    && let LiteralType::Number(r_val) = right
-                {
-                    Ok(LiteralType::Number(l_val % r_val))
-                } else {
-                    report(
-                        expr.operator.line,
-                        "",
-                        &format!("Both operands of '%' must be a number, got {left}, {right}"),
+                _ => {
+                    self.reporter.error(expr.operator.line, &format!(
+                            "Strings can only be repeated a non-negative integer number of times, got {}.",
+                            right.to_debug_string()
+                        ),
                     );
                     Err(Exit::RuntimeError)
                 }
+            },
+            TokenType::Minus | TokenType::Slash | TokenType::Percentage | TokenType::Star => {
+                let (l_val, r_val) =
+                    number_operands(&expr.operator, &left, &right, &self.reporter)?;
+                Ok(LiteralType::Number(match expr.operator.token_type {
+                    TokenType::Minus => l_val - r_val,
+                    TokenType::Slash => l_val / r_val,
+                    TokenType::Percentage => l_val % r_val,
+                    _ => l_val * r_val,
+                }))
             }
-            TokenType::Star => {
-                if let LiteralType::Number(l_val) = left
-                    && let LiteralType::Number(r_val) = right
-                {
-                    Ok(LiteralType::Number(l_val * r_val))
-                } else {
-                    report(
-                        expr.operator.line,
-                        "",
-                        &format!("Both operands of '*' must be a number, got {left}, {right}"),
-                    );
-                    Err(Exit::RuntimeError)
+            TokenType::Div => {
+                let (l_val, r_val) =
+                    number_operands(&expr.operator, &left, &right, &self.reporter)?;
+                if r_val == 0.0 {
+                    self.reporter
+                        .error(expr.operator.line, "Integer division by zero");
+                    return Err(Exit::RuntimeError);
                 }
+                Ok(LiteralType::Number((l_val / r_val).floor()))
             }
             TokenType::Plus => {
                 if let LiteralType::Number(l_val) = left
@@ -88,68 +235,39 @@ impl expr::Visitor<Result<LiteralType, Exit>> for Interpreter {
                 {
                     Ok(LiteralType::Number(l_val + r_val))
                 } else if let LiteralType::String(l_val) = &left
-                    && let LiteralType::String(r_val) = right
+                    && let LiteralType::String(r_val) = &right
                 {
-                    Ok(LiteralType::String(format!("{l_val}{r_val}")))
+                    Ok(LiteralType::String(format!("{l_val}{r_val}").into()))
                 } else {
-                    report(
-                        expr.operator.line,
-                        "",
-                        &format!(
-                            "Both operands of '*' must be a number or string, got {left}, {right}"
-                        ),
-                    );
-                    Err(Exit::RuntimeError)
+                    Err(operand_error(
+                        &expr.operator,
+                        "a number or string",
+                        &[&left, &right],
+                        &self.reporter,
+                    ))
                 }
             }
-            TokenType::Greater => Ok(LiteralType::Boolean(match (&left, &right) {
-                (LiteralType::Number(l_val), LiteralType::Number(r_val)) => l_val > r_val,
-                _ => {
-                    report(
-                        expr.operator.line,
-                        "",
-                        &format!("Can't compare {left}, {right}"),
-                    );
-
-                    return Err(Exit::RuntimeError);
-                }
-            })),
-            TokenType::Less => Ok(LiteralType::Boolean(match (&left, &right) {
-                (LiteralType::Number(l_val), LiteralType::Number(r_val)) => l_val < r_val,
-                _ => {
-                    report(
-                        expr.operator.line,
-                        "",
-                        &format!("Can't compare {left}, {right}"),
-                    );
-
-                    return Err(Exit::RuntimeError);
-                }
-            })),
-            TokenType::GreaterEqual => Ok(LiteralType::Boolean(match (&left, &right) {
-                (LiteralType::Number(l_val), LiteralType::Number(r_val)) => l_val >= r_val,
-                _ => {
-                    report(
+            TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual => {
+                if let (LiteralType::Number(l_val), LiteralType::Number(r_val)) = (&left, &right) {
+                    Ok(LiteralType::Boolean(match expr.operator.token_type {
+                        TokenType::Greater => l_val > r_val,
+                        TokenType::GreaterEqual => l_val >= r_val,
+                        TokenType::Less => l_val < r_val,
+                        _ => l_val <= r_val,
+                    }))
+                } else {
+                    Err(comparison_error(
                         expr.operator.line,
-                        "",
-                        &format!("Can't compare {left}, {right}"),
-                    );
-
-                    return Err(Exit::RuntimeError);
+                        &expr.operator.lexeme,
+                        &left,
+                        &right,
+                        &self.reporter,
+                    ))
                 }
-            })),
-            TokenType::LessEqual => Ok(LiteralType::Boolean(match (&left, &right) {
-                (LiteralType::Number(l_val), LiteralType::Number(r_val)) => l_val <= r_val,
-                _ => {
-                    report(
-                        expr.operator.line,
-                        "",
-                        &format!("Can't compare {left}, {right}"),
-                    );
-
-                    return Err(Exit::RuntimeError);
-                }
-            })),
+            }
             TokenType::EqualEqual => Ok(LiteralType::Boolean(self.is_equal(&left, &right))),
             TokenType::BangEqual => Ok(LiteralType::Boolean(!self.is_equal(&left, &right))),
 
@@ -164,38 +282,79 @@ impl expr::Visitor<Result<LiteralType, Exit>> for Interpreter {
             arguments.push(self.evaluate(argument)?);
         }
 
-        match callee {
-            LiteralType::NativeFunction(func) => {
-                func.check_arity(arguments.len(), &expr.paren)?;
-                func.call(self, &arguments)
-            }
-            LiteralType::Callable(Callable::Function(func)) => {
-                func.check_arity(arguments.len(), &expr.paren)?;
-                func.call(self, &arguments)
-            }
-            LiteralType::Callable(Callable::Class(class)) => {
-                class.check_arity(arguments.len(), &expr.paren)?;
-                class.call(self, &arguments)
-            }
-            _ => {
-                report(expr.paren.line, "", "Can only call functions/methods");
-                Err(Exit::RuntimeError)
-            }
-        }
+        self.call_value(&callee, &arguments, &expr.paren)
     }
 
     fn visit_grouping(&mut self, expr: &Grouping) -> Result<LiteralType, Exit> {
         self.evaluate(&expr.expr)
     }
 
+    fn visit_index(&mut self, expr: &expr::Index) -> Result<LiteralType, Exit> {
+        let object = self.evaluate(&expr.object)?;
+        let index = self.evaluate(&expr.index)?;
+        if let LiteralType::List(list) = object {
+            let index = self.list_index(list.borrow().len(), &index, &expr.bracket)?;
+            Ok(list.borrow()[index].clone())
+        } else {
+            self.reporter
+                .error(expr.bracket.line, "Only lists can be indexed.");
+            Err(Exit::RuntimeError)
+        }
+    }
+
+    fn visit_slice(&mut self, expr: &expr::Slice) -> Result<LiteralType, Exit> {
+        let object = self.evaluate(&expr.object)?;
+        let LiteralType::List(list) = object else {
+            self.reporter
+                .error(expr.bracket.line, "Only lists can be sliced.");
+            return Err(Exit::RuntimeError);
+        };
+        // the bounds can change the list's length, so it's only read once they're evaluated
+        let start = expr
+            .start
+            .as_deref()
+            .map(|e| self.evaluate(e))
+            .transpose()?;
+        let end = expr.end.as_deref().map(|e| self.evaluate(e)).transpose()?;
+        let list = list.borrow();
+        let start = self.slice_bound(start, 0, list.len(), &expr.bracket)?;
+        let end = self.slice_bound(end, list.len(), list.len(), &expr.bracket)?;
+        let elements = list[start..end.max(start)].to_vec();
+        Ok(LiteralType::List(Rc::new(RefCell::new(elements))))
+    }
+
+    fn visit_interpolation(&mut self, expr: &expr::Interpolation) -> Result<LiteralType, Exit> {
+        let mut text = String::new();
+        for part in expr.parts.iter() {
+            text.push_str(&self.evaluate(part)?.to_string());
+        }
+        Ok(LiteralType::String(text.into()))
+    }
+
+    fn visit_list(&mut self, expr: &expr::List) -> Result<LiteralType, Exit> {
+        let mut elements = Vec::new();
+        for element in expr.elements.iter() {
+            elements.push(self.evaluate(element)?);
+        }
+        Ok(LiteralType::List(Rc::new(RefCell::new(elements))))
+    }
+
     fn visit_literal(&self, expr: &Literal) -> Result<LiteralType, Exit> {
         Ok(expr.value.clone())
     }
 
+    /// `a or b` is `a` if it's truthy and `b` otherwise, `a and b` is `a` if it's falsy
+    /// and `b` otherwise, so chains return the first operand deciding the result,
+    /// e.g. `nil or 0 or 1` is `0` since only `nil` and `false` are falsy
     fn visit_logical(&mut self, expr: &Logical) -> Result<LiteralType, Exit> {
         let left = self.evaluate(&expr.left)?;
 
-        if matches!(expr.operator.token_type, TokenType::Or | TokenType::BarBar) {
+        if expr.operator.token_type == TokenType::QuestionQuestion {
+            // Unlike `or` this only checks for nil, so `false ?? 1` is `false`
+            if !matches!(left, LiteralType::Nil) {
+                return Ok(left);
+            }
+        } else if matches!(expr.operator.token_type, TokenType::Or | TokenType::BarBar) {
             if self.is_truthy(&left) {
                 return Ok(left);
             }
@@ -212,27 +371,44 @@ impl expr::Visitor<Result<LiteralType, Exit>> for Interpreter {
         match expr.operator.token_type {
             TokenType::Minus => match right {
                 LiteralType::Number(val) => Ok(LiteralType::Number(-val)),
-                val => {
-                    report(
-                        expr.operator.line,
-                        "",
-                        &format!("Operand of '-' must be a number, got {val}"),
-                    );
-                    Err(Exit::RuntimeError)
-                }
+                #[cfg(feature = "bigint")]
+                LiteralType::BigInt(val) => Ok(LiteralType::BigInt(-val)),
+                val => Err(operand_error(
+                    &expr.operator,
+                    "a number",
+                    &[&val],
+                    &self.reporter,
+                )),
             },
             TokenType::Bang => Ok(LiteralType::Boolean(!self.is_truthy(&right))),
             _ => unreachable!(),
         }
     }
 
+    fn visit_optional_chain(&mut self, expr: &expr::OptionalChain) -> Result<LiteralType, Exit> {
+        match self.evaluate(&expr.expr) {
+            Err(Exit::ShortCircuit) => Ok(LiteralType::Nil),
+            result => result,
+        }
+    }
+
+    fn visit_unwrap(&mut self, expr: &expr::Unwrap) -> Result<LiteralType, Exit> {
+        match self.evaluate(&expr.expr)? {
+            LiteralType::Nil => {
+                self.reporter.error(expr.bang.line, "Unexpected nil.");
+                Err(Exit::RuntimeError)
+            }
+            value => Ok(value),
+        }
+    }
+
     fn visit_variable(&mut self, expr: &expr::Variable) -> Result<LiteralType, Exit> {
-        self.look_up_variable(&expr.name, &Expr::Variable(expr.clone()))
+        self.look_up_variable(&expr.name, expr.uuid)
     }
 
     fn visit_assignment(&mut self, expr: &expr::Assignment) -> Result<LiteralType, Exit> {
         let value = self.evaluate(&expr.value)?;
-        let distance = self.locals.get(&Expr::Assignment(expr.clone()));
+        let distance = self.locals.get(&expr.uuid);
         if let Some(d) = distance {
             self.environment
                 .borrow_mut()
@@ -240,17 +416,35 @@ impl expr::Visitor<Result<LiteralType, Exit>> for Interpreter {
         } else {
             self.globals
                 .borrow_mut()
-                .assign(&expr.name, value.clone())?;
+                .assign(&expr.name, value.clone(), &self.reporter)?;
         }
         Ok(value)
     }
 
     fn visit_get(&mut self, expr: &expr::Get) -> Result<LiteralType, Exit> {
         let object = self.evaluate(&expr.object)?;
+        if expr.optional && matches!(object, LiteralType::Nil) {
+            return Err(Exit::ShortCircuit);
+        }
         if let LiteralType::Callable(Callable::Instance(instance)) = object {
-            instance.borrow().get(&expr.name)
+            if expr.optional {
+                Ok(instance.borrow().get_or_nil(&expr.name))
+            } else {
+                instance.borrow().get(&expr.name, &self.reporter)
+            }
+        } else if let Some(method) = stdlib::primitive_method(&object, &expr.name.lexeme) {
+            Ok(LiteralType::NativeFunction(method))
+        } else if expr.optional {
+            Ok(LiteralType::Nil)
+        } else if let LiteralType::String(_) | LiteralType::Number(_) = object {
+            self.reporter.error(
+                expr.name.line,
+                &format!("Undefined property {}.", expr.name.lexeme),
+            );
+            Err(Exit::RuntimeError)
         } else {
-            report(expr.name.line, "", "Only instances have properties.");
+            self.reporter
+                .error(expr.name.line, "Only instances have properties.");
             Err(Exit::RuntimeError)
         }
     }
@@ -262,38 +456,58 @@ impl expr::Visitor<Result<LiteralType, Exit>> for Interpreter {
             instance.borrow_mut().set(&expr.name, &value);
             Ok(value)
         } else {
-            report(expr.name.line, "", "Only instances have fields.");
+            self.reporter
+                .error(expr.name.line, "Only instances have fields.");
+            Err(Exit::RuntimeError)
+        }
+    }
+
+    fn visit_set_index(&mut self, expr: &expr::SetIndex) -> Result<LiteralType, Exit> {
+        let object = self.evaluate(&expr.object)?;
+        let index = self.evaluate(&expr.index)?;
+        // the value can change the list's length, so the index is checked after evaluating it
+        let value = self.evaluate(&expr.value)?;
+        if let LiteralType::List(list) = object {
+            let index = self.list_index(list.borrow().len(), &index, &expr.bracket)?;
+            list.borrow_mut()[index] = value.clone();
+            Ok(value)
+        } else {
+            self.reporter
+                .error(expr.bracket.line, "Only lists can be indexed.");
             Err(Exit::RuntimeError)
         }
     }
 
     fn visit_self_expr(&mut self, expr: &expr::SelfExpr) -> Result<LiteralType, Exit> {
-        self.look_up_variable(&expr.keyword, &Expr::SelfExpr(expr.clone()))
+        self.look_up_variable(&expr.keyword, expr.uuid)
     }
 
     fn visit_super_expr(&mut self, expr: &expr::SuperExpr) -> Result<LiteralType, Exit> {
-        let distance = self.locals.get(&Expr::SuperExpr(expr.clone()));
+        let distance = self.locals.get(&expr.uuid);
         if distance.is_none() {
-            report(expr.keyword.line, "", "Couldn't find superclass");
+            self.reporter
+                .error(expr.keyword.line, "Couldn't find superclass");
             return Err(Exit::RuntimeError);
         }
         let superclass = self.environment.borrow_mut().get_at(
             *distance.unwrap(),
-            &Token {
-                token_type: TokenType::SuperKW,
-                lexeme: "super".to_string(),
-                literal: LiteralType::Nil,
-                line: expr.method.line,
-            },
+            &Token::new(
+                TokenType::SuperKW,
+                "super".to_string(),
+                LiteralType::Nil,
+                expr.method.line,
+            ),
+            &self.reporter,
         )?;
         let object = self.environment.borrow().get_at(
             distance.unwrap() - 1,
-            &Token {
-                token_type: TokenType::SelfKW,
-                lexeme: "self".to_string(),
-                literal: LiteralType::Nil,
-                line: expr.method.line,
-            },
+            &Token::new(
+                TokenType::SelfKW,
+                "self".to_string(),
+                LiteralType::Nil,
+                expr.method.line,
+            ),
+            &self.reporter,
         )?;
 
         if let LiteralType::Callable(Callable::Class(c)) = &superclass {
@@ -302,9 +516,8 @@ impl expr::Visitor<Result<LiteralType, Exit>> for Interpreter {
                 match method {
                     Some(m) => return Ok(LiteralType::Callable(Callable::Function(m.bind(ins)))),
                     None => {
-                        report(
+                        self.reporter.error(
                             expr.method.line,
-                            "",
                             &format!("Undefined property {}.", expr.method.lexeme),
                         );
                         return Err(Exit::RuntimeError);
@@ -332,17 +545,107 @@ impl stmt::Visitor<Result<(), Exit>> for Interpreter {
         Ok(())
     }
 
+    fn visit_import(&mut self, stmt: &stmt::Import) -> Result<(), Exit> {
+        stmt.statements
+            .iter()
+            .try_for_each(|stmt| self.execute(stmt))
+    }
+
     fn visit_while(&mut self, stmt: &stmt::While) -> Result<(), Exit> {
         loop {
             let eval = self.evaluate(&stmt.condition)?;
             if !self.is_truthy(&eval) {
                 break;
             }
-            self.execute(&stmt.body)?;
+            self.count_step(stmt.keyword.line)?;
+            let result = self.execute(&stmt.body);
+            if ends_loop(stmt.label.as_ref(), result)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_for_range(&mut self, stmt: &stmt::ForRange) -> Result<(), Exit> {
+        let start = self.range_bound(&stmt.start, "start", &stmt.keyword)?;
+        let end = self.range_bound(&stmt.end, "end", &stmt.keyword)?;
+        let step = match &stmt.step {
+            Some(step) => self.range_bound(step, "step", &stmt.keyword)?,
+            None => 1.0,
+        };
+        if step == 0.0 {
+            self.reporter
+                .error(stmt.keyword.line, "Range step can't be 0.");
+            return Err(Exit::RuntimeError);
+        }
+
+        let mut i = start;
+        while (step > 0.0 && i < end) || (step < 0.0 && i > end) {
+            self.count_step(stmt.keyword.line)?;
+            // a fresh variable each iteration so closures keep the value they saw
+            let mut env = Environment::new_with_enclosing(self.environment.clone());
+            env.define(stmt.name.lexeme.clone(), LiteralType::Number(i));
+            let result = self.execute_block(std::slice::from_ref(&stmt.body), env);
+            if ends_loop(stmt.label.as_ref(), result)? {
+                break;
+            }
+            i += step;
         }
         Ok(())
     }
 
+    fn visit_for_each(&mut self, stmt: &stmt::ForEach) -> Result<(), Exit> {
+        let iterable = self.evaluate(&stmt.iterable)?;
+        match iterable {
+            LiteralType::List(list) => {
+                // changing the list in the body doesn't change what gets iterated
+                let items = list.borrow().clone();
+                for item in items {
+                    if self.run_for_body(stmt, item)? {
+                        break;
+                    }
+                }
+            }
+            LiteralType::Callable(Callable::Instance(instance)) => {
+                let iterator = self.call_method(&instance, "iter", &stmt.keyword)?;
+                let LiteralType::Callable(Callable::Instance(iterator)) = iterator else {
+                    self.reporter.error(stmt.keyword.line, &format!(
+                            "iter() must return an instance with hasNext() and next() methods, got {}.",
+                            iterator.to_debug_string()
+                        ),
+                    );
+                    return Err(Exit::RuntimeError);
+                };
+                while {
+                    let has_next = self.call_method(&iterator, "hasNext", &stmt.keyword)?;
+                    self.is_truthy(&has_next)
+                } {
+                    let item = self.call_method(&iterator, "next", &stmt.keyword)?;
+                    if self.run_for_body(stmt, item)? {
+                        break;
+                    }
+                }
+            }
+            _ => {
+                self.reporter.error(
+                    stmt.keyword.line,
+                    &format!(
+                        "Can only iterate over lists and instances with an iter() method, got {}.",
+                        iterable.to_debug_string()
+                    ),
+                );
+                return Err(Exit::RuntimeError);
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_break(&mut self, stmt: &stmt::Break) -> Result<(), Exit> {
+        Err(Exit::Break(
+            stmt.label.as_ref().map(|label| label.lexeme.clone()),
+        ))
+    }
+
     fn visit_var(&mut self, stmt: &stmt::Var) -> Result<(), Exit> {
         let val = self.evaluate(&stmt.initializer)?;
         self.environment
@@ -351,6 +654,26 @@ impl stmt::Visitor<Result<(), Exit>> for Interpreter {
         Ok(())
     }
 
+    fn visit_multi_var(&mut self, stmt: &stmt::MultiVar) -> Result<(), Exit> {
+        for var in &stmt.vars {
+            self.visit_var(var)?;
+        }
+        Ok(())
+    }
+
+    fn visit_try(&mut self, stmt: &stmt::Try) -> Result<(), Exit> {
+        let result = self.execute_block(
+            &stmt.body,
+            Environment::new_with_enclosing(self.environment.clone()),
+        );
+        if let Err(Exit::Throw(value)) = result {
+            let mut env = Environment::new_with_enclosing(self.environment.clone());
+            env.define(stmt.name.lexeme.clone(), value);
+            return self.execute_block(&stmt.handler, env);
+        }
+        result
+    }
+
     fn visit_block(&mut self, block: &stmt::Block) -> Result<(), Exit> {
         self.execute_block(
             &block.statements,
@@ -373,9 +696,25 @@ impl stmt::Visitor<Result<(), Exit>> for Interpreter {
         if let Some(sc) = &stmt.superclass {
             superclass = self.evaluate(sc)?;
             if let LiteralType::Callable(Callable::Class(c)) = &superclass {
-                s_c = Some(c.clone());
+                // `class A < B {}` after `class B < A {}` would chain back to the old `A`
+                let mut ancestor = Some(c.as_ref());
+                while let Some(class) = ancestor {
+                    if class.name == stmt.name.lexeme {
+                        self.reporter.error(
+                            stmt.name.line,
+                            &format!(
+                                "Class '{}' can't inherit from itself through '{}'.",
+                                stmt.name.lexeme, c.name
+                            ),
+                        );
+                        return Err(Exit::RuntimeError);
+                    }
+                    ancestor = class.superclass.as_deref();
+                }
+                s_c = Some(LoxClass::clone(c));
             } else {
-                report(stmt.name.line, "", "Superclass must be a class.");
+                self.reporter
+                    .error(stmt.name.line, "Superclass must be a class.");
                 return Err(Exit::RuntimeError);
             }
         }
@@ -399,7 +738,7 @@ impl stmt::Visitor<Result<(), Exit>> for Interpreter {
                 let function = LoxFunction::new(
                     method.clone(),
                     Rc::clone(&self.environment),
-                    method.name.lexeme == "new",
+                    is_initializer(&method.name.lexeme),
                 );
                 methods.insert(method.name.lexeme.clone(), function);
             }
@@ -412,9 +751,11 @@ impl stmt::Visitor<Result<(), Exit>> for Interpreter {
             self.environment = enclosing;
         }
 
-        self.environment
-            .borrow_mut()
-            .assign(&stmt.name, LiteralType::Callable(Callable::Class(class)))?;
+        self.environment.borrow_mut().assign(
+            &stmt.name,
+            LiteralType::Callable(Callable::Class(Rc::new(class))),
+            &self.reporter,
+        )?;
 
         Ok(())
     }
@@ -426,94 +767,503 @@ impl stmt::Visitor<Result<(), Exit>> for Interpreter {
 
 impl Interpreter {
     pub fn new() -> Self {
-        let globals = Rc::new(RefCell::new(Environment::new()));
-        // Std functions
-        globals.borrow_mut().define(
-            String::from("clock"),
-            LiteralType::NativeFunction(NativeFunction {
-                arity: 0,
-                callable: |_, _| {
-                    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-                    LiteralType::Number(timestamp.as_millis() as f64)
-                },
-            }),
-        );
-        globals.borrow_mut().define(
-            String::from("print"),
-            LiteralType::NativeFunction(NativeFunction {
-                arity: 1,
-                callable: |_, args| {
-                    println!("{}", args[0]);
-                    LiteralType::Nil
-                },
-            }),
-        );
-        Self {
-            environment: globals.clone(),
-            globals,
-            locals: HashMap::new(),
+        InterpreterBuilder::new().build()
+    }
+
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<LiteralType, Exit> {
+        self.steps = 0;
+        self.declare_globals(statements);
+        self.hoist_functions(statements)?;
+
+        for stmt in statements.iter().filter(|stmt| !is_function(stmt)) {
+            match self.execute(stmt) {
+                Ok(()) => {}
+                Err(Exit::Return(value)) => return Ok(value),
+                Err(Exit::RuntimeError) => {
+                    self.print_backtrace();
+                    return Err(Exit::RuntimeError);
+                }
+                Err(e) => return Err(e),
+            }
         }
+
+        Ok(LiteralType::Nil)
     }
 
-    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), Exit> {
-        for stmt in statements {
-            self.execute(stmt)?;
+    /// Prints the calls a runtime error unwound through, innermost first
+    fn print_backtrace(&mut self) {
+        for (name, line) in self.call_stack.drain(..).rev() {
+            eprintln!("    at {name} ({})", self.reporter.source_location(line));
         }
+    }
 
-        Ok(())
+    /// Calls `callee` after checking it's callable with that many arguments
+    pub fn call_value(
+        &mut self,
+        callee: &LiteralType,
+        arguments: &[LiteralType],
+        paren: &Token,
+    ) -> Result<LiteralType, Exit> {
+        match callee {
+            LiteralType::NativeFunction(func) => {
+                func.check_arity(arguments.len(), paren, &self.reporter)?;
+                func.call(self, arguments, paren)
+            }
+            LiteralType::Callable(Callable::Function(func)) => {
+                func.check_arity(arguments.len(), paren, &self.reporter)?;
+                func.call(self, arguments, paren)
+            }
+            LiteralType::Callable(Callable::Class(class)) => {
+                class.check_arity(arguments.len(), paren, &self.reporter)?;
+                class.call(self, arguments, paren)
+            }
+            _ => {
+                self.reporter
+                    .error(paren.line, "Can only call functions/methods");
+                Err(Exit::RuntimeError)
+            }
+        }
+    }
+
+    /// Position of `index` in a list of length `len`, negative ones count from the end
+    pub(crate) fn list_index(
+        &self,
+        len: usize,
+        index: &LiteralType,
+        bracket: &Token,
+    ) -> Result<usize, Exit> {
+        match index {
+            LiteralType::Number(i)
+                if i.fract() == 0.0 && *i >= -(len as f64) && *i < len as f64 =>
+            {
+                if *i < 0.0 {
+                    Ok(len - (-*i as usize))
+                } else {
+                    Ok(*i as usize)
+                }
+            }
+            LiteralType::Number(i) => {
+                self.reporter.error(
+                    bracket.line,
+                    &format!("Index {i} out of range for list of length {len}."),
+                );
+                Err(Exit::RuntimeError)
+            }
+            _ => {
+                self.reporter.error(
+                    bracket.line,
+                    &format!(
+                        "List index must be a number, got {}.",
+                        index.to_debug_string()
+                    ),
+                );
+                Err(Exit::RuntimeError)
+            }
+        }
+    }
+
+    fn range_bound(&mut self, bound: &Expr, name: &str, keyword: &Token) -> Result<f64, Exit> {
+        match self.evaluate(bound)? {
+            LiteralType::Number(n) => Ok(n),
+            other => {
+                self.reporter.error(
+                    keyword.line,
+                    &format!(
+                        "Range {name} must be a number, got {}.",
+                        other.to_debug_string()
+                    ),
+                );
+                Err(Exit::RuntimeError)
+            }
+        }
+    }
+
+    /// Start or end of a slice, negative ones count from the end and ones
+    /// out of range are clamped like in Python
+    fn slice_bound(
+        &self,
+        bound: Option<LiteralType>,
+        default: usize,
+        len: usize,
+        bracket: &Token,
+    ) -> Result<usize, Exit> {
+        let Some(bound) = bound else {
+            return Ok(default);
+        };
+        match bound {
+            LiteralType::Number(i) if i.fract() == 0.0 => {
+                let i = if i < 0.0 { i + len as f64 } else { i };
+                Ok(i.clamp(0.0, len as f64) as usize)
+            }
+            other => {
+                self.reporter.error(
+                    bracket.line,
+                    &format!(
+                        "Slice bounds must be integers, got {}.",
+                        other.to_debug_string()
+                    ),
+                );
+                Err(Exit::RuntimeError)
+            }
+        }
     }
 
     fn execute(&mut self, stmt: &Stmt) -> Result<(), Exit> {
+        if self.trace {
+            eprintln!("[trace] {}", AstPrinter.print_stmt(stmt));
+        }
         stmt.accept(self)?;
         Ok(())
     }
 
     pub fn execute_block(&mut self, statements: &[Stmt], env: Environment) -> Result<(), Exit> {
+        self.execute_block_in(statements, Rc::new(RefCell::new(env)))
+    }
+
+    pub fn execute_block_in(
+        &mut self,
+        statements: &[Stmt],
+        env: Rc<RefCell<Environment>>,
+    ) -> Result<(), Exit> {
         let previous = Rc::clone(&self.environment);
-        self.environment = Rc::new(RefCell::new(env));
+        self.environment = env;
 
-        let result = statements.iter().try_for_each(|stat| self.execute(stat));
+        let result = self.hoist_functions(statements).and_then(|()| {
+            statements
+                .iter()
+                .filter(|stmt| !is_function(stmt))
+                .try_for_each(|stmt| self.execute(stmt))
+        });
 
         self.environment = previous;
         result
     }
 
-    fn is_equal(&mut self, left: &LiteralType, right: &LiteralType) -> bool {
+    /// Declares all globals up front so functions reading them before their
+    /// declaration ran get a proper error instead of an undefined variable
+    fn declare_globals(&mut self, statements: &[Stmt]) {
+        for stmt in statements {
+            match stmt {
+                Stmt::Var(var) => self.globals.borrow_mut().declare(var.name.lexeme.clone()),
+                Stmt::MultiVar(multi_var) => {
+                    for var in &multi_var.vars {
+                        self.globals.borrow_mut().declare(var.name.lexeme.clone());
+                    }
+                }
+                Stmt::Import(import) => self.declare_globals(&import.statements),
+                _ => {}
+            }
+        }
+    }
+
+    /// Defines the functions declared in `statements` before running any of them,
+    /// so they can call each other regardless of their order
+    fn hoist_functions(&mut self, statements: &[Stmt]) -> Result<(), Exit> {
+        statements
+            .iter()
+            .filter(|stmt| is_function(stmt))
+            .try_for_each(|stmt| self.execute(stmt))
+    }
+
+    /// Runs the body of `stmt` with its variable set to `item`, `true` if that ended the loop
+    fn run_for_body(&mut self, stmt: &stmt::ForEach, item: LiteralType) -> Result<bool, Exit> {
+        self.count_step(stmt.keyword.line)?;
+        let mut env = Environment::new_with_enclosing(self.environment.clone());
+        env.define(stmt.name.lexeme.clone(), item);
+        let result = self.execute_block(std::slice::from_ref(&stmt.body), env);
+        ends_loop(stmt.label.as_ref(), result)
+    }
+
+    /// Counts a loop iteration or function call against the `execution_limit`
+    pub(crate) fn count_step(&mut self, line: usize) -> Result<(), Exit> {
+        self.steps += 1;
+        if self.execution_limit.is_some_and(|max| self.steps > max) {
+            self.reporter.error(line, "Execution limit exceeded.");
+            return Err(Exit::RuntimeError);
+        }
+        Ok(())
+    }
+
+    /// Calls the method `name` of `instance` without arguments, erroring if it has none
+    fn call_method(
+        &mut self,
+        instance: &Rc<RefCell<LoxInstance>>,
+        name: &str,
+        token: &Token,
+    ) -> Result<LiteralType, Exit> {
+        let Some(method) = bound_method(instance, name) else {
+            self.reporter.error(
+                token.line,
+                &format!("{} has no {name}() method.", instance.borrow().class.name),
+            );
+            return Err(Exit::RuntimeError);
+        };
+        method.check_arity(0, token, &self.reporter)?;
+        method.call(self, &[], token)
+    }
+
+    /// Calls the method overloading `operator` if the operand it's called on is an instance
+    /// whose class has one, e.g. `a + b` is `a.plus(b)` and `a != b` is `!a.equals(b)`
+    ///
+    /// All comparisons use `lessThan`: `a >= b` is `!a.lessThan(b)`, while `a > b` is
+    /// `b.lessThan(a)` and `a <= b` is `!b.lessThan(a)`, so those are called on the right
+    /// operand. Comparisons always result in booleans.
+    fn call_operator_method(
+        &mut self,
+        operator: &Token,
+        left: &LiteralType,
+        right: &LiteralType,
+    ) -> Option<Result<LiteralType, Exit>> {
+        let name = operator_method_name(&operator.token_type)?;
+        let (receiver, argument) = match operator.token_type {
+            TokenType::Greater | TokenType::LessEqual => (right, left),
+            _ => (left, right),
+        };
+        let LiteralType::Callable(Callable::Instance(instance)) = receiver else {
+            return None;
+        };
+        let method = bound_method(instance, name)?;
+        let result = method
+            .check_arity(1, operator, &self.reporter)
+            .and_then(|()| method.call(self, std::slice::from_ref(argument), operator));
+        Some(match operator.token_type {
+            TokenType::EqualEqual | TokenType::Less | TokenType::Greater => {
+                result.map(|value| LiteralType::Boolean(self.is_truthy(&value)))
+            }
+            TokenType::BangEqual | TokenType::GreaterEqual | TokenType::LessEqual => {
+                result.map(|value| LiteralType::Boolean(!self.is_truthy(&value)))
+            }
+            _ => result,
+        })
+    }
+
+    /// Values of different types are never equal, callables are compared by identity and
+    /// lists are equal if they have equal elements
+    ///
+    /// Numbers follow IEEE 754: `0/0 == 0/0` is `false` and `0 == -0` is `true`.
+    /// The book's jlox uses Java's `Double.equals` instead, which has it the other way around.
+    pub(crate) fn is_equal(&mut self, left: &LiteralType, right: &LiteralType) -> bool {
+        self.is_equal_nested(left, right, &mut Vec::new())
+    }
+
+    /// `is_equal` inside the pairs of lists in `comparing`, which are being compared further up
+    ///
+    /// Meeting one of those pairs again means the lists contain themselves, they're taken
+    /// as equal there so comparing them ends and the other elements decide.
+    fn is_equal_nested(
+        &mut self,
+        left: &LiteralType,
+        right: &LiteralType,
+        comparing: &mut Vec<(*const (), *const ())>,
+    ) -> bool {
         match (&left, &right) {
+            #[cfg(feature = "bigint")]
+            (LiteralType::BigInt(_), _) | (_, LiteralType::BigInt(_)) => {
+                crate::bigint::equals(left, right)
+            }
             (LiteralType::Number(l_val), LiteralType::Number(r_val)) => l_val == r_val,
             (LiteralType::String(l_val), LiteralType::String(r_val)) => l_val == r_val,
             (LiteralType::Boolean(l_val), LiteralType::Boolean(r_val)) => l_val == r_val,
             (LiteralType::Nil, LiteralType::Nil) => true,
+            (
+                LiteralType::Callable(Callable::Function(l_val)),
+                LiteralType::Callable(Callable::Function(r_val)),
+            ) => l_val.is_same(r_val),
+            (
+                LiteralType::Callable(Callable::Class(l_val)),
+                LiteralType::Callable(Callable::Class(r_val)),
+            ) => Rc::ptr_eq(l_val, r_val),
+            (
+                LiteralType::Callable(Callable::Instance(l_val)),
+                LiteralType::Callable(Callable::Instance(r_val)),
+            ) => Rc::ptr_eq(l_val, r_val),
+            (LiteralType::NativeFunction(l_val), LiteralType::NativeFunction(r_val)) => {
+                std::ptr::fn_addr_eq(l_val.callable, r_val.callable)
+                    && match (&l_val.receiver, &r_val.receiver) {
+                        (Some(l_receiver), Some(r_receiver)) => {
+                            self.is_equal_nested(l_receiver, r_receiver, comparing)
+                        }
+                        (l_receiver, r_receiver) => l_receiver.is_none() && r_receiver.is_none(),
+                    }
+            }
+            (LiteralType::List(l_val), LiteralType::List(r_val)) => {
+                let pair = (Rc::as_ptr(l_val).cast(), Rc::as_ptr(r_val).cast());
+                if Rc::ptr_eq(l_val, r_val) || comparing.contains(&pair) {
+                    return true;
+                }
+                let (l_val, r_val) = (l_val.borrow(), r_val.borrow());
+                if l_val.len() != r_val.len() {
+                    return false;
+                }
+                comparing.push(pair);
+                let equal = l_val
+                    .iter()
+                    .zip(r_val.iter())
+                    .all(|(l, r)| self.is_equal_nested(l, r, comparing));
+                comparing.pop();
+                equal
+            }
             _ => false,
         }
     }
 
     pub fn evaluate(&mut self, expr: &Expr) -> Result<LiteralType, Exit> {
-        expr.accept(self)
+        let value = expr.accept(self)?;
+        if self.trace {
+            eprintln!(
+                "[trace] {} => {}",
+                AstPrinter.print_expr(expr),
+                value.to_debug_string()
+            );
+        }
+        Ok(value)
     }
 
-    pub fn resolve(&mut self, expr: &Expr, depth: usize) {
-        self.locals.insert(expr.clone(), depth);
+    /// Evaluates `expr` with `env` as the current environment
+    pub fn evaluate_in(
+        &mut self,
+        expr: &Expr,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Result<LiteralType, Exit> {
+        let previous = std::mem::replace(&mut self.environment, Rc::clone(env));
+        let result = self.evaluate(expr);
+        self.environment = previous;
+        result
     }
 
-    pub fn look_up_variable(&mut self, name: &Token, expr: &Expr) -> Result<LiteralType, Exit> {
-        let distance = self.locals.get(expr);
+    /// Records that the variable used by the expression with `uuid` is `depth` scopes up
+    pub fn resolve(&mut self, uuid: usize, depth: usize) {
+        self.locals.insert(uuid, depth);
+    }
+
+    pub fn look_up_variable(&mut self, name: &Token, uuid: usize) -> Result<LiteralType, Exit> {
+        let distance = self.locals.get(&uuid);
         if let Some(d) = distance {
-            self.environment.borrow_mut().get_at(*d, name)
+            self.environment
+                .borrow_mut()
+                .get_at(*d, name, &self.reporter)
         } else {
-            self.globals.borrow().get(name)
+            self.globals.borrow().get(name, &self.reporter)
         }
     }
 
-    /// Everything but false and nil is truthy
-    fn is_truthy(&mut self, expr: &LiteralType) -> bool {
+    /// Everything but false and nil is truthy, with `empty_is_falsy` also
+    /// `0`, `""` and empty lists aren't
+    pub fn is_truthy(&mut self, expr: &LiteralType) -> bool {
         match expr {
-            LiteralType::String(_) => true,
-            LiteralType::Number(_) => true,
+            LiteralType::String(s) => !(self.empty_is_falsy && s.is_empty()),
+            LiteralType::Number(n) => !(self.empty_is_falsy && *n == 0.0),
+            #[cfg(feature = "bigint")]
+            LiteralType::BigInt(n) => !(self.empty_is_falsy && num_traits::Zero::is_zero(n)),
             LiteralType::Nil => false,
             LiteralType::Boolean(val) => *val,
             LiteralType::Callable(_) => true,
             LiteralType::NativeFunction(_) => true,
+            LiteralType::List(list) => !(self.empty_is_falsy && list.borrow().is_empty()),
+        }
+    }
+}
+
+/// Whether running the body of the loop with `label` gave `result` that ends the loop,
+/// exits meant for something else are passed on
+fn ends_loop(label: Option<&Token>, result: Result<(), Exit>) -> Result<bool, Exit> {
+    match result {
+        Ok(()) => Ok(false),
+        Err(Exit::Break(None)) => Ok(true),
+        Err(Exit::Break(Some(target))) if label.is_some_and(|label| label.lexeme == target) => {
+            Ok(true)
+        }
+        Err(exit) => Err(exit),
+    }
+}
+
+/// The method `name` of `instance`'s class with `self` being `instance`
+fn bound_method(instance: &Rc<RefCell<LoxInstance>>, name: &str) -> Option<LoxFunction> {
+    let method = instance
+        .borrow()
+        .class
+        .find_method(name)?
+        .bind(Rc::clone(instance));
+    Some(method)
+}
+
+/// Name of the method a class defines to overload `operator`
+fn operator_method_name(operator: &TokenType) -> Option<&'static str> {
+    match operator {
+        TokenType::Plus => Some("plus"),
+        TokenType::Minus => Some("minus"),
+        TokenType::Star => Some("times"),
+        TokenType::Slash => Some("divide"),
+        TokenType::EqualEqual | TokenType::BangEqual => Some("equals"),
+        TokenType::Less | TokenType::LessEqual | TokenType::Greater | TokenType::GreaterEqual => {
+            Some("lessThan")
         }
+        _ => None,
+    }
+}
+
+fn is_function(stmt: &Stmt) -> bool {
+    matches!(stmt, Stmt::Function(_))
+}
+
+/// Both operands of an arithmetic `operator`, erroring if they aren't numbers
+fn number_operands(
+    operator: &Token,
+    left: &LiteralType,
+    right: &LiteralType,
+    reporter: &ErrorReporter,
+) -> Result<(f64, f64), Exit> {
+    match (left, right) {
+        (LiteralType::Number(l_val), LiteralType::Number(r_val)) => Ok((*l_val, *r_val)),
+        _ => Err(operand_error(
+            operator,
+            "a number",
+            &[left, right],
+            reporter,
+        )),
     }
 }
+
+/// Reports operands of the wrong type for a unary or binary `operator`, `expected` is
+/// e.g. "a number"
+pub(crate) fn operand_error(
+    operator: &Token,
+    expected: &str,
+    operands: &[&LiteralType],
+    reporter: &ErrorReporter,
+) -> Exit {
+    let subject = if operands.len() == 1 {
+        "Operand"
+    } else {
+        "Both operands"
+    };
+    let got: Vec<String> = operands.iter().map(|o| o.to_debug_string()).collect();
+    reporter.error(
+        operator.line,
+        &format!(
+            "{subject} of '{}' must be {expected}, got {}",
+            operator.lexeme,
+            got.join(", ")
+        ),
+    );
+    Exit::RuntimeError
+}
+
+/// Reports values that can't be ordered, `operator` is how they were compared like `<`
+pub(crate) fn comparison_error(
+    line: usize,
+    operator: &str,
+    left: &LiteralType,
+    right: &LiteralType,
+    reporter: &ErrorReporter,
+) -> Exit {
+    reporter.error(
+        line,
+        &format!(
+            "Can't compare {} {operator} {}",
+            left.to_debug_string(),
+            right.to_debug_string()
+        ),
+    );
+    Exit::RuntimeError
+}
@@ -1,62 +1,265 @@
+mod ast_printer;
+#[cfg(feature = "bigint")]
+mod bigint;
 mod environment;
-mod expr;
+mod error_reporter;
+pub mod expr;
+mod formatter;
 mod interpreter;
 mod lox_callable;
 mod parser;
 mod resolver;
 mod scanner;
-mod stmt;
+mod stdlib;
+pub mod stmt;
 mod token;
 
-use std::io::Write;
+use std::fmt::Display;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
 use std::{fs, io};
 
-use crate::interpreter::Interpreter;
+pub use crate::error_reporter::{Diagnostic, ErrorReporter, Severity, diagnostics_to_json};
+pub use crate::interpreter::{Exit, Interpreter, InterpreterBuilder};
+pub use crate::lox_callable::NativeFunction;
+pub use crate::parser::ParseError;
+pub use crate::token::{LiteralType, Token};
+#[cfg(feature = "bigint")]
+pub use num_bigint::BigInt;
+
+use crate::formatter::SourceFormatter;
 use crate::parser::Parser;
 use crate::resolver::Resolver;
 use crate::scanner::Scanner;
 
-pub fn run_file(arg: &str) {
-    let content = fs::read_to_string(arg);
-    run(&content.unwrap());
+/// Flags changing how scripts are run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    /// Print how long each phase took to stderr
+    pub time: bool,
+    /// The final expression of a function body without a `;` is returned
+    pub implicit_return: bool,
+    /// Print each executed statement and evaluated expression to stderr
+    pub trace: bool,
+    /// `0`, `""` and empty lists are falsy
+    pub empty_is_falsy: bool,
+    /// Return the diagnostics of `--check` without printing them, for them to be printed as JSON
+    pub json: bool,
+    /// Warn about local variables shadowing one of an enclosing scope
+    pub warn_shadowing: bool,
+}
+
+#[derive(Debug)]
+pub enum LoxError {
+    /// Reading the script at the given path failed
+    Io(String, io::Error),
+}
+
+impl Display for LoxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoxError::Io(path, err) => write!(f, "could not read '{path}': {err}"),
+        }
+    }
+}
+
+pub fn run_file(arg: &str, options: Options) -> Result<(), LoxError> {
+    run_file_with_args(arg, &[], options)
 }
 
-pub fn run_prompt() {
+/// Runs the script at `path` with `args` in its global list `args`
+pub fn run_file_with_args(path: &str, args: &[String], options: Options) -> Result<(), LoxError> {
+    let content = fs::read_to_string(path).map_err(|e| LoxError::Io(path.to_owned(), e))?;
+    let mut interpreter = new_interpreter(options, args);
+    run_in(&mut interpreter, &content, Some(Path::new(path)), options);
+    Ok(())
+}
+
+/// Runs the whole program read from stdin, `args` are the arguments passed to it
+pub fn run_stdin(args: &[String], options: Options) -> Result<(), LoxError> {
+    let mut content = String::new();
+    io::stdin()
+        .read_to_string(&mut content)
+        .map_err(|e| LoxError::Io("<stdin>".to_owned(), e))?;
+    run_in(&mut new_interpreter(options, args), &content, None, options);
+    Ok(())
+}
+
+pub fn run_prompt(options: Options) {
+    prompt(&mut new_interpreter(options, &[]), options);
+}
+
+/// Runs the script at `path`, then starts the prompt with its globals still defined
+pub fn run_file_then_prompt(path: &str, args: &[String], options: Options) -> Result<(), LoxError> {
+    let content = fs::read_to_string(path).map_err(|e| LoxError::Io(path.to_owned(), e))?;
+    let mut interpreter = new_interpreter(options, args);
+    run_in(&mut interpreter, &content, Some(Path::new(path)), options);
+    prompt(&mut interpreter, options);
+    Ok(())
+}
+
+/// Reads and runs lines until stdin ends, all of them sharing `interpreter`
+fn prompt(interpreter: &mut Interpreter, options: Options) {
     loop {
         print!(">> ");
         let mut line = String::new();
         let _ = io::stdout().flush();
-        io::stdin().read_line(&mut line).unwrap();
-        run(&line);
+        if io::stdin().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        run_in(interpreter, &line, None, options);
     }
 }
 
-fn run(content: &str) {
-    let mut scanner = Scanner::new(content.to_owned());
-    let tokens = scanner.scan_tokens();
+/// Scans, parses and resolves the script at `path` without running it
+///
+/// Returns the errors and warnings found, which also get printed as usual unless
+/// [`Options::json`] is set. The script is fine if none of them is an error.
+pub fn check_file(path: &str, options: Options) -> Result<Vec<Diagnostic>, LoxError> {
+    let content = fs::read_to_string(path).map_err(|e| LoxError::Io(path.to_owned(), e))?;
+    let mut interpreter = Interpreter::new();
+    if options.json {
+        interpreter.reporter = ErrorReporter::quiet();
+    }
+    let result = parse_and_resolve(
+        &content,
+        Some(Path::new(path)),
+        options,
+        &mut interpreter,
+        &mut Vec::new(),
+    );
+    if let Err(summary) = result
+        && !options.json
+    {
+        eprintln!("{summary}");
+    }
+    Ok(interpreter.reporter.diagnostics())
+}
 
-    let mut parser = Parser::new(tokens);
-    let stmts = parser.parse();
+/// Formats the script at `path`, `None` if it doesn't parse
+pub fn format_file(path: &str) -> Result<Option<String>, LoxError> {
+    let content = fs::read_to_string(path).map_err(|e| LoxError::Io(path.to_owned(), e))?;
+    let tokens = Scanner::new(content).scan_tokens();
+    let stmts = Parser::with_path(tokens, Path::new(path)).parse();
+    Ok(stmts
+        .ok()
+        .map(|stmts| SourceFormatter::default().format(&stmts)))
+}
 
-    if let Ok(stmts) = stmts {
-        let mut interpreter = Interpreter::new();
-        let mut resolver = Resolver::new(&mut interpreter);
-        if resolver.resolve_statements(&stmts).is_err() {
-            eprintln!("Parsing error while resolving");
-            return;
-        }
-        if interpreter.interpret(&stmts).is_err() {
-            eprintln!("Runtime Error");
+/// Parses `src` without running it, e.g. for tools working on the AST
+///
+/// Errors are reported to stderr like when running a script.
+pub fn parse_str(src: &str) -> Result<Vec<stmt::Stmt>, ParseError> {
+    Parser::new(Scanner::new(src.to_owned()).scan_tokens()).parse()
+}
+
+/// Scans `src` keeping comments on the tokens following them, e.g. for linters
+pub fn scan_with_comments(src: &str) -> Vec<Token> {
+    Scanner::new(src.to_owned()).keep_comments().scan_tokens()
+}
+
+/// Runs `content` with an interpreter configured by the embedder, e.g. a sandboxed one
+///
+/// Returns the value the script returned at the top level (see
+/// [`InterpreterBuilder::allow_top_level_return`]), `nil` if it didn't, and `None` on errors.
+/// The errors are kept by the interpreter's [`ErrorReporter`] until the next run.
+pub fn run_source(interpreter: &mut Interpreter, content: &str) -> Option<LiteralType> {
+    run_phases(
+        content,
+        None,
+        Options::default(),
+        interpreter,
+        &mut Vec::new(),
+    )
+}
+
+/// An interpreter for the command line, `args` are the arguments passed to the script
+fn new_interpreter(options: Options, args: &[String]) -> Interpreter {
+    let mut interpreter = InterpreterBuilder::new().script_args(args.to_vec()).build();
+    interpreter.trace = options.trace;
+    interpreter.empty_is_falsy = options.empty_is_falsy;
+    interpreter
+}
+
+/// Runs `content` with `interpreter`, which keeps the globals it defines for later runs
+fn run_in(interpreter: &mut Interpreter, content: &str, path: Option<&Path>, options: Options) {
+    let mut timings = Vec::new();
+    let _ = run_phases(content, path, options, interpreter, &mut timings);
+
+    if options.time {
+        for (phase, duration) in timings {
+            eprintln!("{phase:<12} {duration:?}");
         }
-    } else {
-        eprintln!("Parsing error");
     }
 }
 
-fn error(line: usize, message: &str) {
-    report(line, "", message);
+/// Runs all phases on `content`, recording how long each of them took
+fn run_phases(
+    content: &str,
+    path: Option<&Path>,
+    options: Options,
+    interpreter: &mut Interpreter,
+    timings: &mut Vec<(&str, Duration)>,
+) -> Option<LiteralType> {
+    interpreter.reporter.clear();
+    let stmts = match parse_and_resolve(content, path, options, interpreter, timings) {
+        Ok(stmts) => stmts,
+        Err(summary) => {
+            eprintln!("{summary}");
+            return None;
+        }
+    };
+
+    let start = Instant::now();
+    let interpreted = interpreter.interpret(&stmts);
+    timings.push(("interpreting", start.elapsed()));
+    match &interpreted {
+        Err(Exit::Throw(value)) => eprintln!("Uncaught exception: {}", value.to_debug_string()),
+        Err(_) => eprintln!("Runtime Error"),
+        Ok(_) => {}
+    }
+    interpreted.ok()
 }
 
-pub fn report(line: usize, location: &str, message: &str) {
-    eprintln!("[line {}] Error {}: {}", line, location, message);
+/// The phases before running `content`, erroring with a summary line if they found errors
+fn parse_and_resolve(
+    content: &str,
+    path: Option<&Path>,
+    options: Options,
+    interpreter: &mut Interpreter,
+    timings: &mut Vec<(&str, Duration)>,
+) -> Result<Vec<stmt::Stmt>, &'static str> {
+    let start = Instant::now();
+    let reporter = &interpreter.reporter;
+    let (tokens, line_directives) = Scanner::new(content.to_owned())
+        .with_reporter(reporter.clone())
+        .scan();
+    reporter.set_line_directives(line_directives);
+    timings.push(("scanning", start.elapsed()));
+
+    let start = Instant::now();
+    let mut parser = match path {
+        Some(path) => Parser::with_path(tokens, path),
+        None => Parser::new(tokens),
+    };
+    parser.implicit_return = options.implicit_return;
+    parser.fold_constants = true;
+    parser.reporter = reporter.clone();
+    let stmts = parser.parse();
+    timings.push(("parsing", start.elapsed()));
+
+    if let Ok(stmts) = stmts {
+        let start = Instant::now();
+        let mut resolver = Resolver::new(interpreter);
+        resolver.warn_shadowing = options.warn_shadowing;
+        let resolved = resolver.resolve_statements(&stmts);
+        timings.push(("resolving", start.elapsed()));
+        if resolved.is_err() {
+            return Err("Parsing error while resolving");
+        }
+        Ok(stmts)
+    } else {
+        Err("Parsing error")
+    }
 }
@@ -1,24 +1,38 @@
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::ops::RangeInclusive;
 use std::{cell::RefCell, rc::Rc};
 
 use crate::token::TokenType;
 use crate::{
     environment::Environment,
+    error_reporter::ErrorReporter,
     interpreter::{Exit, Interpreter},
     stmt,
-    token::{LiteralType, Token},
+    token::{LiteralType, Token, format_once},
 };
 
 pub enum Callable {
     Function(LoxFunction),
-    Class(LoxClass),
+    Class(Rc<LoxClass>),
     Instance(Rc<RefCell<LoxInstance>>),
 }
 
+/// Functions and classes show their name only, their closures and methods would
+/// be too noisy and can refer back to them
 impl std::fmt::Debug for Callable {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Callable")
+        match self {
+            Callable::Function(function) => write!(f, "Function({:?})", function.name()),
+            Callable::Class(class) => write!(f, "Class({:?})", class.name),
+            Callable::Instance(instance) => format_once(Rc::as_ptr(instance).cast(), f, |f| {
+                let instance = instance.borrow();
+                f.debug_struct("Instance")
+                    .field("class", &instance.class.name)
+                    .field("fields", &FieldsInOrder(&instance))
+                    .finish()
+            }),
+        }
     }
 }
 
@@ -37,15 +51,23 @@ pub trait LoxCallable {
         &self,
         interpreter: &mut Interpreter,
         arguments: &[LiteralType],
+        paren: &Token,
     ) -> Result<LiteralType, Exit>;
+    /// Minimum number of arguments
     fn arity(&self) -> usize;
-    fn check_arity(&self, args_len: usize, current_token: &Token) -> Result<(), Exit> {
-        if args_len != self.arity() {
-            crate::report(
-                current_token.line,
-                "",
-                &format!("Expected {} arguments but got {}.", self.arity(), args_len),
-            );
+    /// Maximum number of arguments, `None` if there is no limit
+    fn max_arity(&self) -> Option<usize> {
+        Some(self.arity())
+    }
+    fn check_arity(
+        &self,
+        args_len: usize,
+        current_token: &Token,
+        reporter: &ErrorReporter,
+    ) -> Result<(), Exit> {
+        let (min, max) = (self.arity(), self.max_arity());
+        if args_len < min || max.is_some_and(|max| args_len > max) {
+            reporter.error(current_token.line, &arity_mismatch(min, max, args_len));
             return Err(Exit::RuntimeError);
         }
 
@@ -53,9 +75,19 @@ pub trait LoxCallable {
     }
 }
 
+/// Message for calling something taking `min` to `max` arguments with `got` ones
+pub fn arity_mismatch(min: usize, max: Option<usize>, got: usize) -> String {
+    let expected = match max {
+        Some(max) if max == min => format!("{min}"),
+        Some(max) => format!("{min} to {max}"),
+        None => format!("at least {min}"),
+    };
+    format!("Expected {expected} arguments but got {got}.")
+}
+
 #[derive(Clone, Debug)]
 pub struct LoxFunction {
-    declaration: Box<stmt::Function>,
+    declaration: Rc<stmt::Function>,
     closure: Rc<RefCell<Environment>>,
     is_initializer: bool,
 }
@@ -67,12 +99,22 @@ impl LoxFunction {
         is_initializer: bool,
     ) -> Self {
         Self {
-            declaration: Box::new(declaration),
+            declaration: Rc::new(declaration),
             closure,
             is_initializer,
         }
     }
 
+    pub fn name(&self) -> &str {
+        &self.declaration.name.lexeme
+    }
+
+    /// Two functions are the same if they share their declaration and closure
+    pub fn is_same(&self, other: &LoxFunction) -> bool {
+        Rc::ptr_eq(&self.declaration, &other.declaration)
+            && Rc::ptr_eq(&self.closure, &other.closure)
+    }
+
     pub fn bind(&self, instance: Rc<RefCell<LoxInstance>>) -> LoxFunction {
         let environment = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(
             &self.closure,
@@ -89,24 +131,79 @@ impl LoxFunction {
     }
 }
 
+impl Display for LoxFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<fn {}>", self.name())
+    }
+}
+
 impl LoxCallable for LoxFunction {
     fn call(
         &self,
         interpreter: &mut Interpreter,
         arguments: &[LiteralType],
+        paren: &Token,
     ) -> Result<LiteralType, Exit> {
-        let mut env = Environment::new_with_enclosing(Rc::clone(&self.closure));
-        for (param, arg) in self.declaration.params.iter().zip(arguments) {
-            env.define(param.lexeme.clone(), arg.clone());
+        interpreter.count_step(paren.line)?;
+        interpreter
+            .call_stack
+            .push((self.name().to_string(), paren.line));
+        let result = self.run(interpreter, arguments);
+        // keep the frame for the backtrace if a runtime error unwinds through it
+        if !matches!(result, Err(Exit::RuntimeError)) {
+            interpreter.call_stack.pop();
+        }
+        result
+    }
+
+    fn arity(&self) -> usize {
+        self.declaration.arity()
+    }
+
+    fn max_arity(&self) -> Option<usize> {
+        self.declaration.max_arity()
+    }
+}
+
+impl LoxFunction {
+    /// Binds the arguments and runs the body
+    fn run(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &[LiteralType],
+    ) -> Result<LiteralType, Exit> {
+        let env = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(
+            &self.closure,
+        ))));
+        for (i, (param, default)) in self.declaration.params.iter().enumerate() {
+            let value = match (arguments.get(i), default) {
+                (Some(arg), _) => arg.clone(),
+                (None, Some(default)) => interpreter.evaluate_in(default, &env)?,
+                // the arity was checked before calling
+                (None, None) => unreachable!(),
+            };
+            env.borrow_mut().define(param.lexeme.clone(), value);
+        }
+        if let Some(rest) = &self.declaration.rest {
+            let remaining = arguments
+                .get(self.declaration.params.len()..)
+                .unwrap_or_default()
+                .to_vec();
+            env.borrow_mut().define(
+                rest.lexeme.clone(),
+                LiteralType::List(Rc::new(RefCell::new(remaining))),
+            );
         }
 
-        let i = interpreter.execute_block(&self.declaration.body, env);
+        let i = interpreter.execute_block_in(&self.declaration.body, env);
 
         match &i {
             Ok(_) => (),
             Err(e) => {
                 if let Exit::Return(r) = e {
                     return Ok(r.clone());
+                } else if let Exit::Throw(value) = e {
+                    return Err(Exit::Throw(value.clone()));
                 } else {
                     return Err(Exit::RuntimeError);
                 }
@@ -115,45 +212,69 @@ impl LoxCallable for LoxFunction {
         if self.is_initializer {
             return self.closure.borrow().get_at(
                 0,
-                &Token {
-                    token_type: TokenType::SelfKW,
-                    lexeme: String::from("self"),
-                    literal: LiteralType::Nil,
-                    line: self.declaration.name.line,
-                },
+                &Token::new(
+                    TokenType::SelfKW,
+                    String::from("self"),
+                    LiteralType::Nil,
+                    self.declaration.name.line,
+                ),
+                &interpreter.reporter,
             );
         }
         Ok(LiteralType::Nil)
     }
-
-    fn arity(&self) -> usize {
-        self.declaration.params.len()
-    }
 }
 
 #[derive(Clone, Debug)]
 pub struct NativeFunction {
-    pub arity: usize,
-    pub callable: fn(&mut Interpreter, &[LiteralType]) -> LiteralType,
+    /// Allowed number of arguments, an end of `usize::MAX` means there is no limit
+    pub arity: RangeInclusive<usize>,
+    pub callable: fn(&mut Interpreter, &[LiteralType], &Token) -> Result<LiteralType, Exit>,
+    /// Value the function is bound to as a method, it's passed as first argument
+    pub receiver: Option<Box<LiteralType>>,
+}
+
+/// Natives don't know their name, so they all print the same
+impl Display for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn>")
+    }
 }
 
 impl LoxCallable for NativeFunction {
     fn arity(&self) -> usize {
-        self.arity
+        *self.arity.start()
+    }
+
+    fn max_arity(&self) -> Option<usize> {
+        Some(*self.arity.end()).filter(|&max| max != usize::MAX)
     }
 
     fn call(
         &self,
         interpreter: &mut Interpreter,
         args: &[LiteralType],
+        paren: &Token,
     ) -> Result<LiteralType, Exit> {
-        Ok((self.callable)(interpreter, args))
+        match &self.receiver {
+            Some(receiver) => {
+                let mut with_receiver = vec![receiver.as_ref().clone()];
+                with_receiver.extend_from_slice(args);
+                (self.callable)(interpreter, &with_receiver, paren)
+            }
+            None => (self.callable)(interpreter, args, paren),
+        }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct LoxClass {
     pub name: String,
+    /// Copy of the superclass as it was when this class was declared
+    ///
+    /// A class can only inherit from one that already exists, so the chain always ends. The
+    /// resolver reports `class A < A {}` and the interpreter a chain that would loop back
+    /// to a class of the same name, like `class A < B {}` after `class B < A {}`.
     pub superclass: Option<Box<LoxClass>>,
     pub methods: HashMap<String, LoxFunction>,
 }
@@ -183,28 +304,52 @@ impl LoxClass {
             }
         }
     }
+
+    /// The class's `new` or `init` method, or the closest superclass's
+    pub fn initializer(&self) -> Option<&LoxFunction> {
+        INITIALIZER_NAMES
+            .iter()
+            .find_map(|name| self.methods.get(*name))
+            .or_else(|| self.superclass.as_ref()?.initializer())
+    }
+}
+
+/// Initializers are called `new`, but `init` like in the book works as well
+pub const INITIALIZER_NAMES: [&str; 2] = ["new", "init"];
+
+pub fn is_initializer(name: &str) -> bool {
+    INITIALIZER_NAMES.contains(&name)
 }
 
 impl LoxCallable for LoxClass {
     fn arity(&self) -> usize {
-        if let Some(initializer) = self.find_method("new") {
+        if let Some(initializer) = self.initializer() {
             initializer.arity()
         } else {
             0
         }
     }
 
+    fn max_arity(&self) -> Option<usize> {
+        if let Some(initializer) = self.initializer() {
+            initializer.max_arity()
+        } else {
+            Some(0)
+        }
+    }
+
     fn call(
         &self,
         interpreter: &mut Interpreter,
         args: &[LiteralType],
+        paren: &Token,
     ) -> Result<LiteralType, Exit> {
         let instance = Rc::new(RefCell::new(LoxInstance::new(Rc::new(self.clone()))));
 
-        if let Some(initializer) = self.find_method("new") {
+        if let Some(initializer) = self.initializer() {
             initializer
                 .bind(Rc::clone(&instance))
-                .call(interpreter, args)?;
+                .call(interpreter, args, paren)?;
         }
 
         Ok(LiteralType::Callable(Callable::Instance(Rc::clone(
@@ -223,6 +368,24 @@ impl Display for LoxClass {
 pub struct LoxInstance {
     pub class: Rc<LoxClass>,
     pub fields: HashMap<String, LiteralType>,
+    /// Names of the fields in the order they were first set, so printing is deterministic
+    field_order: Vec<String>,
+}
+
+/// Debug formats the fields of an instance like a map, in the order they were first set
+pub(crate) struct FieldsInOrder<'a>(pub &'a LoxInstance);
+
+impl std::fmt::Debug for FieldsInOrder<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(
+                self.0
+                    .field_order
+                    .iter()
+                    .filter_map(|name| Some((name, self.0.fields.get(name)?))),
+            )
+            .finish()
+    }
 }
 
 impl LoxInstance {
@@ -230,36 +393,58 @@ impl LoxInstance {
         LoxInstance {
             class,
             fields: HashMap::new(),
+            field_order: Vec::new(),
         }
     }
 
-    pub fn get(&self, name: &Token) -> Result<LiteralType, Exit> {
-        if self.fields.contains_key(&name.lexeme) {
-            Ok(self.fields.get(&name.lexeme).unwrap().clone())
-        } else if let Some(method) = self.class.find_method(&name.lexeme) {
-            Ok(LiteralType::Callable(Callable::Function(
-                method.bind(Rc::new(RefCell::new(self.to_owned()))),
-            )))
+    /// The field called `name` or else the method bound to this instance
+    fn lookup(&self, name: &Token) -> Option<LiteralType> {
+        if let Some(field) = self.fields.get(&name.lexeme) {
+            Some(field.clone())
         } else {
-            println!("Fields set: {:#?}", self.fields);
-            crate::report(
+            self.class.find_method(&name.lexeme).map(|method| {
+                LiteralType::Callable(Callable::Function(
+                    method.bind(Rc::new(RefCell::new(self.to_owned()))),
+                ))
+            })
+        }
+    }
+
+    pub fn get(&self, name: &Token, reporter: &ErrorReporter) -> Result<LiteralType, Exit> {
+        if let Some(value) = self.lookup(name) {
+            Ok(value)
+        } else {
+            let fields: Vec<&str> = self
+                .field_order
+                .iter()
+                .filter(|field| self.fields.contains_key(*field))
+                .map(String::as_str)
+                .collect();
+            let available = if fields.is_empty() {
+                format!("{} has no fields.", self.class)
+            } else {
+                format!("Fields of {} are: {}.", self.class, fields.join(", "))
+            };
+            reporter.error(
                 name.line,
-                "",
-                &format!("Undefined property {}.", name.lexeme),
+                &format!("Undefined property {}. {available}", name.lexeme),
             );
             Err(Exit::RuntimeError)
         }
     }
 
-    pub fn get_or_nil(&self, name: &Token) -> Result<LiteralType, Exit> {
-        if self.fields.contains_key(&name.lexeme) {
-            Ok(self.fields.get(&name.lexeme).unwrap().clone())
-        } else {
-            Ok(LiteralType::Nil)
-        }
+    /// Like [`get`](Self::get) but missing properties are nil instead of an error
+    pub fn get_or_nil(&self, name: &Token) -> LiteralType {
+        self.lookup(name).unwrap_or(LiteralType::Nil)
     }
 
     pub fn set(&mut self, name: &Token, value: &LiteralType) {
-        self.fields.insert(name.lexeme.clone(), value.clone());
+        if self
+            .fields
+            .insert(name.lexeme.clone(), value.clone())
+            .is_none()
+        {
+            self.field_order.push(name.lexeme.clone());
+        }
     }
 }
@@ -1,16 +1,113 @@
-use jlox_rs::{run_file, run_prompt};
+use jlox_rs::{
+    Options, Severity, check_file, diagnostics_to_json, format_file, run_file_then_prompt,
+    run_file_with_args, run_prompt, run_stdin,
+};
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // everything after the script is passed to it, even if it looks like one of our flags
+    let script = args
+        .iter()
+        .position(|arg| !arg.starts_with('-') || arg == "-");
+    let script_args = match script {
+        Some(i) if args[i] != "fmt" => args.split_off(i + 1),
+        _ => Vec::new(),
+    };
+
+    let mut options = Options::default();
+    args.retain(|arg| match arg.as_str() {
+        "--time" => {
+            options.time = true;
+            false
+        }
+        "--implicit-return" => {
+            options.implicit_return = true;
+            false
+        }
+        "--trace" => {
+            options.trace = true;
+            false
+        }
+        "--empty-is-falsy" => {
+            options.empty_is_falsy = true;
+            false
+        }
+        "--json" => {
+            options.json = true;
+            false
+        }
+        "--warn-shadowing" => {
+            options.warn_shadowing = true;
+            false
+        }
+        _ => true,
+    });
+
     match args.len() {
+        0 => {
+            run_prompt(options);
+        }
         1 => {
-            run_prompt();
+            let result = if args[0] == "-" {
+                run_stdin(&script_args, options)
+            } else {
+                run_file_with_args(&args[0], &script_args, options)
+            };
+            if let Err(e) = result {
+                eprintln!("Error: {e}");
+                // EX_IOERR
+                std::process::exit(74);
+            }
+        }
+        2 if args[0] == "-i" => {
+            if let Err(e) = run_file_then_prompt(&args[1], &script_args, options) {
+                eprintln!("Error: {e}");
+                std::process::exit(74);
+            }
         }
-        2 => {
-            run_file(&args[1]);
+        // checking doesn't run the script, so it can't take arguments
+        2 if args[0] == "--check" && script_args.is_empty() => {
+            match check_file(&args[1], options) {
+                Ok(diagnostics) => {
+                    if options.json {
+                        println!("{}", diagnostics_to_json(&diagnostics));
+                    }
+                    if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+                        // EX_DATAERR
+                        std::process::exit(65);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    std::process::exit(74);
+                }
+            }
         }
+        2 if args[0] == "fmt" => match format_file(&args[1]) {
+            Ok(Some(formatted)) => print!("{formatted}"),
+            Ok(None) => {
+                eprintln!("Parsing error");
+                // EX_DATAERR
+                std::process::exit(65);
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(74);
+            }
+        },
         _ => {
-            println!("Usage: jlox-rs [script]");
+            println!(
+                "Usage: jlox-rs [--time] [--implicit-return] [--trace] [--empty-is-falsy] [--warn-shadowing] [script | -] [args...]"
+            );
+            println!(
+                "       jlox-rs [--time] [--implicit-return] [--trace] [--empty-is-falsy] [--warn-shadowing] -i script [args...]"
+            );
+            println!(
+                "       jlox-rs [--implicit-return] [--warn-shadowing] [--json] --check script"
+            );
+            println!("       jlox-rs fmt script");
+            // EX_USAGE
+            std::process::exit(64);
         }
     }
 }
@@ -1,8 +1,17 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
 use crate::{
+    error_reporter::ErrorReporter,
     expr::{
-        self, Assignment, Binary, Expr, Get, Grouping, Literal, Logical, SelfExpr, Set, SuperExpr,
-        Unary, Variable,
+        self, Assignment, Binary, Expr, Get, Grouping, Index, List, Literal, Logical, SelfExpr,
+        Set, SetIndex, Slice, SuperExpr, Unary, Variable,
     },
+    lox_callable::is_initializer,
+    scanner::Scanner,
     stmt::{self, Block, Class, Expression, Stmt, Var},
     token::{
         LiteralType, Token,
@@ -13,8 +22,53 @@ use crate::{
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    /// File the tokens come from, used to resolve relative imports
+    path: Option<PathBuf>,
+    /// Files which were already imported, each file only gets imported once
+    imported: HashSet<PathBuf>,
+    /// Whether the final expression of a function body without `;` gets returned
+    pub implicit_return: bool,
+    /// Replace calls with a known result like `"abc".length()` by that result, which is
+    /// off for tools like the formatter that need the tree as written
+    pub fold_constants: bool,
+    /// Where syntax errors go, imported files report to the same one
+    pub reporter: ErrorReporter,
+    /// How many nested expressions and statements are currently being parsed
+    depth: usize,
 }
 
+/// Deeper nesting is an error, so that neither parsing nor the later phases
+/// walking the tree recursively overflow the stack
+///
+/// Every operator of a chain like `1 + 2 + 3` counts as a level too. A level of
+/// parentheses takes about 44KB of stack in debug builds, so 100 stay well within the
+/// 8MB of the main thread.
+const MAX_NESTING: usize = 100;
+
+/// Tokens an expression can start with, listed in errors where an expression was expected
+const EXPRESSION_START: &[TokenType] = &[
+    Identifier,
+    Number,
+    String,
+    Interpolation,
+    True,
+    False,
+    Nil,
+    SelfKW,
+    SuperKW,
+    LeftParen,
+    LeftBracket,
+    Bang,
+    Minus,
+];
+
+/// Tokens only a statement or declaration can start with, anything in `EXPRESSION_START`
+/// starts an expression statement
+const STATEMENT_START: &[TokenType] = &[
+    Class, Var, Fun, If, While, For, Return, Break, Try, Import, LeftBrace,
+];
+
+#[derive(Debug)]
 pub struct ParseError {}
 
 static mut UUID: usize = 0;
@@ -26,9 +80,80 @@ pub fn uuid_next() -> usize {
     }
 }
 
+/// `token` for error messages, with its text if that isn't always the same
+fn describe(token: &Token) -> std::string::String {
+    match token.token_type {
+        Identifier | Number | String => format!("{} '{}'", token.token_type, token.lexeme),
+        ref other => other.to_string(),
+    }
+}
+
+/// `a, b or c` of the token types in all `sets`
+fn expected_one_of(sets: &[&[TokenType]]) -> std::string::String {
+    let names: Vec<_> = sets
+        .iter()
+        .flat_map(|set| set.iter())
+        .map(TokenType::to_string)
+        .collect();
+    match names.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {last}", rest.join(", ")),
+        None => std::string::String::new(),
+    }
+}
+
+/// Result of calling `callee` without arguments if it's known without running anything:
+/// `length()` of a string literal or `size()` of a list of literals, primitive methods
+/// can't be redefined so this is always what the call would return
+fn folded_call(callee: &Expr) -> Option<LiteralType> {
+    let Expr::Get(Get {
+        object,
+        name,
+        optional: false,
+        ..
+    }) = callee
+    else {
+        return None;
+    };
+    let length = match (object.as_ref(), name.lexeme.as_str()) {
+        (
+            Expr::Literal(Literal {
+                value: LiteralType::String(s),
+                ..
+            }),
+            "length",
+        ) => s.chars().count(),
+        (Expr::List(List { elements, .. }), "size")
+            if elements.iter().all(|e| matches!(e, Expr::Literal(_))) =>
+        {
+            elements.len()
+        }
+        _ => return None,
+    };
+    Some(LiteralType::Number(length as f64))
+}
+
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self {
+            tokens,
+            current: 0,
+            path: None,
+            imported: HashSet::new(),
+            implicit_return: false,
+            fold_constants: false,
+            reporter: ErrorReporter::default(),
+            depth: 0,
+        }
+    }
+
+    pub fn with_path(tokens: Vec<Token>, path: &Path) -> Self {
+        let mut parser = Self::new(tokens);
+        if let Ok(path) = path.canonicalize() {
+            parser.imported.insert(path.clone());
+            parser.path = Some(path);
+        }
+        parser
     }
 
     pub fn parse(&mut self) -> Result<Vec<Stmt>, ParseError> {
@@ -56,7 +181,7 @@ impl Parser {
             }
 
             match self.peek().token_type {
-                Class | Fun | Var | For | If | While | Return => {
+                Class | Fun | Var | Break | For | If | Import | Try | While | Return => {
                     return;
                 }
                 _ => {}
@@ -85,12 +210,14 @@ impl Parser {
     }
 
     fn class_declaration(&mut self) -> Result<Stmt, ParseError> {
-        let name = self.consume(&TokenType::Identifier, "Expected class name.")?;
+        let name = self
+            .consume(&TokenType::Identifier, "Expected class name.")?
+            .clone();
 
         let superclass = if self.matches(&[Less]) {
             self.consume(&TokenType::Identifier, "Expected superclassclass name.")?;
             Some(Expr::Variable(Variable {
-                name: self.previous(),
+                name: self.previous().clone(),
                 uuid: uuid_next(),
             }))
         } else {
@@ -111,15 +238,42 @@ impl Parser {
     }
 
     fn fun_declaration(&mut self, kind: &str) -> Result<Stmt, ParseError> {
-        let name = self.consume(&Identifier, &format!("Expected {kind} name"))?;
+        let name = self
+            .consume(&Identifier, &format!("Expected {kind} name"))?
+            .clone();
         self.consume(&LeftParen, &format!("Expected '(' after {kind} name"))?;
-        let mut params = Vec::new();
+        let mut params: Vec<(Token, Option<Expr>)> = Vec::new();
+        let mut rest = None;
         if !self.check(&RightParen) {
             loop {
                 if params.len() >= 255 {
                     self.error(self.peek(), "Can't have more than 255 parameters");
                 }
-                params.push(self.consume(&Identifier, "Expect parameter name.")?);
+                if self.matches(&[DotDotDot]) {
+                    rest = Some(
+                        self.consume(&Identifier, "Expect parameter name after '...'.")?
+                            .clone(),
+                    );
+                    if self.check(&Comma) {
+                        self.error(self.peek(), "Rest parameter must be the last parameter.");
+                        return Err(ParseError {});
+                    }
+                    break;
+                }
+                let param = self.consume(&Identifier, "Expect parameter name.")?.clone();
+                let default = if self.matches(&[Equal]) {
+                    Some(self.expression()?)
+                } else {
+                    if params.last().is_some_and(|(_, default)| default.is_some()) {
+                        self.error(
+                            &param,
+                            "Parameters without default can't follow ones with a default.",
+                        );
+                        return Err(ParseError {});
+                    }
+                    None
+                };
+                params.push((param, default));
                 if !self.matches(&[Comma]) {
                     break;
                 }
@@ -127,42 +281,91 @@ impl Parser {
         }
         self.consume(&RightParen, "Expected ')' after parameters")?;
         self.consume(&LeftBrace, &format!("Expected '{{' before {kind} body"))?;
-        let body = self.block()?;
-        Ok(Stmt::Function(stmt::Function { name, params, body }))
+        let body = if self.implicit_return && !is_initializer(&name.lexeme) {
+            self.nested(Self::function_body)?
+        } else {
+            self.nested(Self::block)?
+        };
+        Ok(Stmt::Function(stmt::Function {
+            name,
+            params,
+            rest,
+            body,
+        }))
     }
 
     fn var_declaration(&mut self) -> Result<Stmt, ParseError> {
-        let name = self.consume(&Identifier, "Expect variable name.")?;
-        let mut initializer = Expr::Literal(Literal {
-            value: LiteralType::Nil,
-            uuid: uuid_next(),
-        });
+        let mut vars = Vec::new();
+        loop {
+            let name = self.consume(&Identifier, "Expect variable name.")?.clone();
+            let mut initializer = Expr::Literal(Literal {
+                value: LiteralType::Nil,
+                uuid: uuid_next(),
+            });
 
-        if self.matches(&[Equal]) {
-            initializer = self.expression()?;
+            if self.matches(&[Equal]) {
+                initializer = self.expression()?;
+            }
+
+            vars.push(Var {
+                name,
+                initializer: Box::new(initializer),
+            });
+            if !self.matches(&[Comma]) {
+                break;
+            }
         }
 
         self.consume(&Semicolon, "Expect semicolon.")?;
 
-        Ok(Stmt::Var(Var {
-            name,
-            initializer: Box::new(initializer),
-        }))
+        if vars.len() == 1 {
+            Ok(Stmt::Var(vars.pop().unwrap()))
+        } else {
+            Ok(Stmt::MultiVar(stmt::MultiVar { vars }))
+        }
     }
 
     fn statement(&mut self) -> Result<Stmt, ParseError> {
+        self.nested(Self::statement_inner)
+    }
+
+    fn statement_inner(&mut self) -> Result<Stmt, ParseError> {
         if self.is_at_end() {
             return self.expression_statement();
         }
         self.advance();
         match self.previous().token_type {
-            For => self.for_statement(),
+            Break => {
+                let keyword = self.previous().clone();
+                let label = if self.matches(&[Identifier]) {
+                    Some(self.previous().clone())
+                } else {
+                    None
+                };
+                self.consume(&Semicolon, "Expect ';' after 'break'.")?;
+                Ok(Stmt::Break(stmt::Break { keyword, label }))
+            }
+            Identifier if self.check(&Colon) => self.labeled_loop(),
+            For => self.for_statement(None),
             If => self.if_statement(),
-            While => self.while_statement(),
+            Import => self.import_statement(),
+            Try => self.try_statement(),
+            While => self.while_statement(None),
             LeftBrace => Ok(Stmt::Block(Block {
                 statements: self.block()?,
             })),
             Return => self.return_statement(),
+            ref other if !EXPRESSION_START.contains(other) => {
+                let found = describe(self.previous());
+                self.error(
+                    self.previous(),
+                    &format!(
+                        "Expect statement, found {found} instead. Expected one of {}.",
+                        expected_one_of(&[STATEMENT_START, EXPRESSION_START])
+                    ),
+                );
+                Err(ParseError {})
+            }
             _ => {
                 self.current -= 1;
                 self.expression_statement()
@@ -170,8 +373,26 @@ impl Parser {
         }
     }
 
+    fn try_statement(&mut self) -> Result<Stmt, ParseError> {
+        self.consume(&LeftBrace, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+        self.consume(&Catch, "Expect 'catch' after try block.")?;
+        self.consume(&LeftParen, "Expect '(' after 'catch'.")?;
+        let name = self
+            .consume(&Identifier, "Expect variable name after '('.")?
+            .clone();
+        self.consume(&RightParen, "Expect ')' after catch variable.")?;
+        self.consume(&LeftBrace, "Expect '{' before catch body.")?;
+        let handler = self.block()?;
+        Ok(Stmt::Try(stmt::Try {
+            body,
+            name,
+            handler,
+        }))
+    }
+
     fn return_statement(&mut self) -> Result<Stmt, ParseError> {
-        let keyword = self.previous();
+        let keyword = self.previous().clone();
         let value = if !self.check(&Semicolon) {
             self.expression()?
         } else {
@@ -187,8 +408,88 @@ impl Parser {
         }))
     }
 
-    fn for_statement(&mut self) -> Result<Stmt, ParseError> {
+    fn import_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
+        let file = self
+            .consume(&String, "Expect path after 'import'.")?
+            .clone();
+        self.consume(&Semicolon, "Expect ';' after import.")?;
+
+        let LiteralType::String(file) = file.literal else {
+            unreachable!()
+        };
+        let file = file.to_string();
+        let base = match &self.path {
+            Some(path) => path.parent().unwrap_or(Path::new("")).to_path_buf(),
+            None => PathBuf::new(),
+        };
+        let path = match base.join(&file).canonicalize() {
+            Ok(path) => path,
+            Err(e) => {
+                self.error(&keyword, &format!("Couldn't import '{file}': {e}"));
+                return Err(ParseError {});
+            }
+        };
+
+        // Already imported (or currently being imported), so there is nothing to splice in
+        if !self.imported.insert(path.clone()) {
+            return Ok(Stmt::Import(stmt::Import {
+                file,
+                statements: Vec::new(),
+            }));
+        }
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                self.error(&keyword, &format!("Couldn't import '{file}': {e}"));
+                return Err(ParseError {});
+            }
+        };
+        let tokens = Scanner::new(content)
+            .with_reporter(self.reporter.clone())
+            .scan_tokens();
+        let mut parser = Parser::new(tokens);
+        parser.path = Some(path);
+        parser.implicit_return = self.implicit_return;
+        parser.fold_constants = self.fold_constants;
+        parser.reporter = self.reporter.clone();
+        parser.imported = std::mem::take(&mut self.imported);
+        let statements = parser.parse();
+        self.imported = parser.imported;
+
+        Ok(Stmt::Import(stmt::Import {
+            file,
+            statements: statements?,
+        }))
+    }
+
+    /// `label: while ...` or `label: for ...`, the label is already consumed
+    fn labeled_loop(&mut self) -> Result<Stmt, ParseError> {
+        let label = self.previous().clone();
+        self.advance();
+        if self.matches(&[While]) {
+            self.while_statement(Some(label))
+        } else if self.matches(&[For]) {
+            self.for_statement(Some(label))
+        } else {
+            self.error(self.peek(), "Expect loop after label.");
+            Err(ParseError {})
+        }
+    }
+
+    fn for_statement(&mut self, label: Option<Token>) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
         self.consume(&LeftParen, "Expect '(' after 'for'.")?;
+        // `for (i in ...)` or `for (var i in ...)`
+        let has_var = self.check(&Var);
+        if self.check_at(has_var as usize, &Identifier) && self.check_at(has_var as usize + 1, &In)
+        {
+            if has_var {
+                self.advance();
+            }
+            return self.for_in(keyword, label);
+        }
         let initializer = if self.matches(&[Semicolon]) {
             None
         } else if self.matches(&[Var]) {
@@ -228,6 +529,8 @@ impl Parser {
         };
 
         body = Stmt::While(stmt::While {
+            keyword,
+            label,
             condition: Box::new(condition),
             body: Box::new(body),
         });
@@ -241,6 +544,42 @@ impl Parser {
         Ok(body)
     }
 
+    /// `for (x in start..end)` counts, `for (x in iterable)` goes over a list or iterator
+    fn for_in(&mut self, keyword: Token, label: Option<Token>) -> Result<Stmt, ParseError> {
+        let name = self.advance().clone();
+        self.advance();
+        let start = self.expression()?;
+        if !self.matches(&[DotDot]) {
+            self.consume(&RightParen, "Expect ')' after iterable.")?;
+            let body = self.statement()?;
+            return Ok(Stmt::ForEach(stmt::ForEach {
+                keyword,
+                label,
+                name,
+                iterable: Box::new(start),
+                body: Box::new(body),
+            }));
+        }
+        let end = self.expression()?;
+        let step = if self.matches(&[DotDot]) {
+            Some(Box::new(self.expression()?))
+        } else {
+            None
+        };
+        self.consume(&RightParen, "Expect ')' after range.")?;
+        let body = self.statement()?;
+
+        Ok(Stmt::ForRange(stmt::ForRange {
+            keyword,
+            label,
+            name,
+            start: Box::new(start),
+            end: Box::new(end),
+            step,
+            body: Box::new(body),
+        }))
+    }
+
     fn if_statement(&mut self) -> Result<Stmt, ParseError> {
         let mut has_paren = false;
         if self.check(&LeftParen) {
@@ -257,6 +596,9 @@ impl Parser {
         let then_branch = self.statement()?;
         let else_branch = if self.matches(&[Else]) {
             Some(self.statement()?)
+        } else if self.matches(&[Elif]) {
+            // `elif` is `else if`
+            Some(self.if_statement()?)
         } else {
             None
         };
@@ -268,7 +610,8 @@ impl Parser {
         }))
     }
 
-    fn while_statement(&mut self) -> Result<Stmt, ParseError> {
+    fn while_statement(&mut self, label: Option<Token>) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
         let mut has_paren = false;
         if self.check(&LeftParen) {
             self.advance();
@@ -284,6 +627,8 @@ impl Parser {
         let body = self.statement()?;
 
         Ok(Stmt::While(stmt::While {
+            keyword,
+            label,
             condition: Box::new(condition),
             body: Box::new(body),
         }))
@@ -300,6 +645,34 @@ impl Parser {
         Ok(statements)
     }
 
+    /// Like `block` but a final expression without `;` gets returned, so `{ a + b }` returns the sum
+    fn function_body(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        let mut statements: Vec<Stmt> = Vec::new();
+
+        while !self.check(&RightBrace) && !self.is_at_end() {
+            if self.at_statement_start() {
+                statements.push(self.declaration()?);
+                continue;
+            }
+
+            let expr = self.expression()?;
+            if self.check(&RightBrace) {
+                statements.push(Stmt::Return(stmt::Return {
+                    keyword: self.peek().clone(),
+                    value: Box::new(expr),
+                }));
+                break;
+            }
+            self.consume(&Semicolon, "Expected ; after expression")?;
+            statements.push(Stmt::Expression(Expression {
+                expr: Box::new(expr),
+            }));
+        }
+
+        self.consume(&RightBrace, "Expect '}' after block.")?;
+        Ok(statements)
+    }
+
     fn expression_statement(&mut self) -> Result<Stmt, ParseError> {
         let val = self.expression()?;
         self.consume(&Semicolon, "Expected ; after expression")?;
@@ -309,15 +682,40 @@ impl Parser {
     }
 
     fn expression(&mut self) -> Result<Expr, ParseError> {
-        self.assignment()
+        self.nested(Self::assignment)
+    }
+
+    /// Runs `parse` one level deeper, erroring instead when that's too deep
+    ///
+    /// Levels added by [`Parser::deeper`] while parsing count until `parse` is done.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        let depth = self.depth;
+        self.deeper()?;
+        let result = parse(self);
+        self.depth = depth;
+        result
+    }
+
+    /// Goes one level deeper for the rest of the enclosing [`Parser::nested`], e.g. for each
+    /// operator of `1 + 2 + 3` since the tree gets deeper with every one of them
+    fn deeper(&mut self) -> Result<(), ParseError> {
+        if self.depth >= MAX_NESTING {
+            self.error(self.peek(), "Too deeply nested.");
+            return Err(ParseError {});
+        }
+        self.depth += 1;
+        Ok(())
     }
 
     fn assignment(&mut self) -> Result<Expr, ParseError> {
-        let expr = self.or()?;
+        let expr = self.nil_coalescing()?;
 
         if self.matches(&[Equal]) {
-            let equals = self.previous();
-            let value = self.assignment()?;
+            let equals = self.previous().clone();
+            let value = self.nested(Self::assignment)?;
 
             if let Expr::Variable(var) = expr {
                 let name = var.name;
@@ -326,12 +724,26 @@ impl Parser {
                     value: Box::new(value),
                     uuid: uuid_next(),
                 }));
-            } else if let Expr::Get(g) = expr {
+            } else if let Expr::Get(Get {
+                object,
+                name,
+                optional: false,
+                ..
+            }) = expr
+            {
                 return Ok(Expr::Set(Set {
                     uuid: uuid_next(),
-                    object: g.object,
+                    object,
+                    value: Box::new(value),
+                    name,
+                }));
+            } else if let Expr::Index(i) = expr {
+                return Ok(Expr::SetIndex(SetIndex {
+                    object: i.object,
+                    bracket: i.bracket,
+                    index: i.index,
                     value: Box::new(value),
-                    name: g.name,
+                    uuid: uuid_next(),
                 }));
             }
 
@@ -342,11 +754,30 @@ impl Parser {
         Ok(expr)
     }
 
+    fn nil_coalescing(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.or()?;
+
+        while self.matches(&[QuestionQuestion]) {
+            self.deeper()?;
+            let operator = self.previous().clone();
+            let right = self.or()?;
+            expr = Expr::Logical(Logical {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+                uuid: uuid_next(),
+            })
+        }
+
+        Ok(expr)
+    }
+
     fn or(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.and()?;
 
         while self.matches(&[Or, BarBar]) {
-            let operator = self.previous();
+            self.deeper()?;
+            let operator = self.previous().clone();
             let right = self.and()?;
             expr = Expr::Logical(Logical {
                 left: Box::new(expr),
@@ -363,8 +794,9 @@ impl Parser {
         let mut expr = self.equality()?;
 
         while self.matches(&[And, AmperAmper]) {
-            let operator = self.previous();
-            let right = self.and()?;
+            self.deeper()?;
+            let operator = self.previous().clone();
+            let right = self.equality()?;
             expr = Expr::Logical(Logical {
                 left: Box::new(expr),
                 operator,
@@ -380,7 +812,8 @@ impl Parser {
         let mut expr = self.comparison();
 
         while self.matches(&[BangEqual, EqualEqual]) {
-            let operator = self.previous();
+            self.deeper()?;
+            let operator = self.previous().clone();
             let right = self.comparison()?;
             expr = Ok(Expr::Binary(Binary {
                 left: Box::new(expr?),
@@ -396,15 +829,21 @@ impl Parser {
     fn comparison(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.term();
 
-        while self.matches(&[Greater, GreaterEqual, Less, LessEqual]) {
-            let operator = self.previous();
+        if self.matches(&[Greater, GreaterEqual, Less, LessEqual]) {
+            let operator = self.previous().clone();
             let right = self.term()?;
             expr = Ok(Expr::Binary(Binary {
                 left: Box::new(expr?),
                 operator,
                 right: Box::new(right),
                 uuid: uuid_next(),
-            }))
+            }));
+
+            // `a < b < c` would compare a boolean with a number at runtime
+            if self.matches(&[Greater, GreaterEqual, Less, LessEqual]) {
+                self.error(self.previous(), "Chained comparison is not allowed; use &&");
+                return Err(ParseError {});
+            }
         }
 
         expr
@@ -414,7 +853,8 @@ impl Parser {
         let mut expr = self.factor();
 
         while self.matches(&[Plus, Minus]) {
-            let operator = self.previous();
+            self.deeper()?;
+            let operator = self.previous().clone();
             let right = self.factor()?;
             expr = Ok(Expr::Binary(Binary {
                 left: Box::new(expr?),
@@ -430,8 +870,9 @@ impl Parser {
     fn factor(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.unary();
 
-        while self.matches(&[Star, Slash, Percentage]) {
-            let operator = self.previous();
+        while self.matches(&[Star, Slash, Div, Percentage]) {
+            self.deeper()?;
+            let operator = self.previous().clone();
             let right = self.unary()?;
             expr = Ok(Expr::Binary(Binary {
                 left: Box::new(expr?),
@@ -446,8 +887,8 @@ impl Parser {
 
     fn unary(&mut self) -> Result<Expr, ParseError> {
         if self.matches(&[Bang, Minus]) {
-            let operator = self.previous();
-            let right = self.unary()?;
+            let operator = self.previous().clone();
+            let right = self.nested(Self::unary)?;
             Ok(Expr::Unary(Unary {
                 operator,
                 right: Box::new(right),
@@ -458,16 +899,71 @@ impl Parser {
         }
     }
 
+    /// Any primary, including groupings and list literals, followed by any sequence of
+    /// calls, property accesses and indexing, e.g. `(make())[0].field(x)`
     fn call(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.primary()?;
+        let mut optional_chain = false;
         loop {
+            // each call, property access and index wraps the expression so far
+            if [LeftParen, Dot, QuestionDot, LeftBracket, Bang]
+                .iter()
+                .any(|token_type| self.check(token_type))
+            {
+                self.deeper()?;
+            }
             if self.matches(&[LeftParen]) {
                 expr = self.finish_call(expr)?;
-            } else if self.matches(&[Dot]) {
-                let name = self.consume(&Identifier, "Expect property name after '.'.")?;
+            } else if self.matches(&[Dot, QuestionDot]) {
+                let optional = self.previous().token_type == QuestionDot;
+                optional_chain |= optional;
+                let name = self
+                    .consume(&Identifier, "Expect property name after '.'.")?
+                    .clone();
                 expr = Expr::Get(Get {
                     object: Box::new(expr),
                     name,
+                    optional,
+                    uuid: uuid_next(),
+                })
+            } else if self.matches(&[LeftBracket]) {
+                let start = if self.check(&Colon) {
+                    None
+                } else {
+                    Some(Box::new(self.expression()?))
+                };
+                if self.matches(&[Colon]) {
+                    let end = if self.check(&RightBracket) {
+                        None
+                    } else {
+                        Some(Box::new(self.expression()?))
+                    };
+                    let bracket = self
+                        .consume(&RightBracket, "Expect ']' after slice.")?
+                        .clone();
+                    expr = Expr::Slice(Slice {
+                        object: Box::new(expr),
+                        bracket,
+                        start,
+                        end,
+                        uuid: uuid_next(),
+                    })
+                } else {
+                    let bracket = self
+                        .consume(&RightBracket, "Expect ']' after index.")?
+                        .clone();
+                    expr = Expr::Index(Index {
+                        object: Box::new(expr),
+                        bracket,
+                        // there's no colon so there is an index
+                        index: start.unwrap(),
+                        uuid: uuid_next(),
+                    })
+                }
+            } else if self.matches(&[Bang]) {
+                expr = Expr::Unwrap(expr::Unwrap {
+                    expr: Box::new(expr),
+                    bang: self.previous().clone(),
                     uuid: uuid_next(),
                 })
             } else {
@@ -475,6 +971,12 @@ impl Parser {
             }
         }
 
+        if optional_chain {
+            expr = Expr::OptionalChain(expr::OptionalChain {
+                expr: Box::new(expr),
+                uuid: uuid_next(),
+            });
+        }
         Ok(expr)
     }
 
@@ -493,7 +995,16 @@ impl Parser {
             }
         }
 
-        let paren = self.consume(&RightParen, "Expect ')' after arguments.")?;
+        let paren = self
+            .consume(&RightParen, "Expect ')' after arguments.")?
+            .clone();
+
+        if self.fold_constants
+            && args.is_empty()
+            && let Some(value) = folded_call(&callee)
+        {
+            return Ok(Expr::Literal(Literal::new(value, uuid_next())));
+        }
 
         Ok(Expr::Call(expr::Call {
             callee: Box::new(callee),
@@ -518,13 +1029,15 @@ impl Parser {
             Ok(Expr::Literal(Literal::new(LiteralType::Nil, uuid_next())))
         } else if self.matches(&[SelfKW]) {
             Ok(Expr::SelfExpr(SelfExpr {
-                keyword: self.previous(),
+                keyword: self.previous().clone(),
                 uuid: uuid_next(),
             }))
         } else if self.matches(&[SuperKW]) {
-            let keyword = self.previous();
+            let keyword = self.previous().clone();
             self.consume(&Dot, "Expect '.' after 'super'.")?;
-            let method = self.consume(&Identifier, "Expect superclass method name.")?;
+            let method = self
+                .consume(&Identifier, "Expect superclass method name.")?
+                .clone();
             Ok(Expr::SuperExpr(SuperExpr {
                 keyword,
                 method,
@@ -532,12 +1045,29 @@ impl Parser {
             }))
         } else if self.matches(&[Number, String]) {
             Ok(Expr::Literal(Literal::new(
-                self.previous().literal,
+                self.previous().literal.clone(),
                 uuid_next(),
             )))
+        } else if self.matches(&[Interpolation]) {
+            self.interpolation()
         } else if self.matches(&[Identifier]) {
             Ok(Expr::Variable(Variable {
-                name: self.previous(),
+                name: self.previous().clone(),
+                uuid: uuid_next(),
+            }))
+        } else if self.matches(&[LeftBracket]) {
+            let mut elements = Vec::new();
+            if !self.check(&RightBracket) {
+                loop {
+                    elements.push(self.expression()?);
+                    if !self.matches(&[Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(&RightBracket, "Expect ']' after list elements.")?;
+            Ok(Expr::List(List {
+                elements,
                 uuid: uuid_next(),
             }))
         } else if self.matches(&[LeftParen]) {
@@ -548,25 +1078,75 @@ impl Parser {
                 uuid: uuid_next(),
             }))
         } else {
-            self.error(self.peek(), "Expect expression.");
+            let found = describe(self.peek());
+            self.error(
+                self.peek(),
+                &format!(
+                    "Expect expression, found {found} instead. Expected one of {}.",
+                    expected_one_of(&[EXPRESSION_START])
+                ),
+            );
             Err(ParseError {})
         }
     }
 
-    fn consume(&mut self, token_type: &TokenType, message: &str) -> Result<Token, ParseError> {
+    /// The rest of `"a ${b} c ${d}"` after the `Interpolation` token for `"a ${`
+    fn interpolation(&mut self) -> Result<Expr, ParseError> {
+        let mut parts = Vec::new();
+        loop {
+            let fragment = self.previous().literal.clone();
+            if !matches!(&fragment, LiteralType::String(s) if s.is_empty()) {
+                parts.push(Expr::Literal(Literal::new(fragment, uuid_next())));
+            }
+            // the rest of the string after an empty `${}`
+            if matches!(self.peek().token_type, Interpolation | String)
+                && self.peek().lexeme.starts_with('}')
+            {
+                self.error(self.peek(), "Expect expression in '${}'.");
+                return Err(ParseError {});
+            }
+            parts.push(self.expression()?);
+            if !self.matches(&[Interpolation]) {
+                break;
+            }
+        }
+        let rest = self
+            .consume(&String, "Expect '}' after interpolated expression.")?
+            .literal
+            .clone();
+        if !matches!(&rest, LiteralType::String(s) if s.is_empty()) {
+            parts.push(Expr::Literal(Literal::new(rest, uuid_next())));
+        }
+        Ok(Expr::Interpolation(expr::Interpolation {
+            parts,
+            uuid: uuid_next(),
+        }))
+    }
+
+    fn consume(&mut self, token_type: &TokenType, message: &str) -> Result<&Token, ParseError> {
         if self.check(token_type) {
             Ok(self.advance())
         } else {
-            self.error(&self.previous(), message);
+            let found = describe(self.peek());
+            self.error(
+                self.previous(),
+                &format!("{}, found {found} instead.", message.trim_end_matches('.')),
+            );
             Err(ParseError {})
         }
     }
 
     fn error(&self, token: &Token, message: &str) {
+        let column = (token.column > 0).then_some(token.column);
         if token.token_type == Eof {
-            crate::error(token.line, &format!(" at end {message}"));
+            self.reporter
+                .error_at(token.line, column, &format!(" at end {message}"));
         } else {
-            crate::error(token.line, &format!(" at '{}' {message}", token.lexeme));
+            self.reporter.error_at(
+                token.line,
+                column,
+                &format!(" at '{}' {message}", token.lexeme),
+            );
         }
     }
 
@@ -589,7 +1169,7 @@ impl Parser {
         }
     }
 
-    fn advance(&mut self) -> Token {
+    fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
             self.current += 1;
         }
@@ -604,7 +1184,21 @@ impl Parser {
         &self.tokens[self.current]
     }
 
-    fn previous(&self) -> Token {
-        self.tokens[self.current - 1].clone()
+    /// Whether the current token starts a statement rather than an expression statement,
+    /// which includes the label of a loop
+    fn at_statement_start(&self) -> bool {
+        STATEMENT_START.contains(&self.peek().token_type)
+            || self.check(&Identifier) && self.check_at(1, &Colon)
+    }
+
+    /// Whether the token `offset` tokens after the current one has type `token_type`
+    fn check_at(&self, offset: usize, token_type: &TokenType) -> bool {
+        self.tokens
+            .get(self.current + offset)
+            .is_some_and(|token| &token.token_type == token_type)
+    }
+
+    fn previous(&self) -> &Token {
+        &self.tokens[self.current - 1]
     }
 }
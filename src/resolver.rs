@@ -1,18 +1,36 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     expr::{self, Expr},
     interpreter::Interpreter,
+    lox_callable::{INITIALIZER_NAMES, arity_mismatch, is_initializer},
     parser::ParseError,
     stmt::{self, Stmt},
-    token::Token,
+    token::{LiteralType, Token},
 };
 
 pub struct Resolver<'a> {
     interpreter: &'a mut Interpreter,
     scopes: Vec<HashMap<String, bool>>,
+    /// Names declared at the top level, which isn't part of `scopes`
+    globals: HashSet<String>,
+    /// Minimum and maximum arities of the functions declared in each scope, the first entry
+    /// is the top level
+    arities: Vec<HashMap<String, (usize, Option<usize>)>>,
+    /// Line, callee and error of direct calls with the wrong number of arguments, only
+    /// reported once the whole program is resolved and the callee turns out never to be
+    /// assigned to, otherwise it could be another function by the time of the call
+    arity_errors: Vec<(usize, String, String)>,
+    /// Names assigned to anywhere in the program
+    assigned: HashSet<String>,
     current_function: FunctionType,
     current_class: ClassType,
+    /// Labels of the loops around the current statement in the current function,
+    /// innermost last and `None` for loops without label
+    loops: Vec<Option<String>>,
+    had_error: bool,
+    /// Warn about `var` declarations shadowing a variable of an enclosing scope
+    pub warn_shadowing: bool,
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -35,18 +53,58 @@ impl Resolver<'_> {
         Resolver {
             interpreter,
             scopes: Vec::new(),
+            globals: HashSet::new(),
+            arities: vec![HashMap::new()],
+            arity_errors: Vec::new(),
+            assigned: HashSet::new(),
             current_function: FunctionType::None,
             current_class: ClassType::None,
+            loops: Vec::new(),
+            had_error: false,
+            warn_shadowing: false,
         }
     }
 
-    pub fn resolve_statements(&mut self, statements: &Vec<Stmt>) -> Result<(), ParseError> {
+    /// Resolves a whole program, errors are reported as they're found and
+    /// resolving continues so all of them get reported at once
+    pub fn resolve_statements(&mut self, statements: &[Stmt]) -> Result<(), ParseError> {
+        self.resolve_body(statements)?;
+        for (line, callee, message) in std::mem::take(&mut self.arity_errors) {
+            if !self.assigned.contains(&callee) {
+                self.error(line, &message);
+            }
+        }
+        if self.had_error {
+            Err(ParseError {})
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Resolves a block, function body or the whole program, functions are declared
+    /// before anything else so they can be called before their declaration
+    fn resolve_body(&mut self, statements: &[Stmt]) -> Result<(), ParseError> {
+        for stmt in statements {
+            if let Stmt::Function(function) = stmt {
+                self.declare(&function.name);
+                self.define(&function.name);
+                self.arities.last_mut().unwrap().insert(
+                    function.name.lexeme.clone(),
+                    (function.arity(), function.max_arity()),
+                );
+            }
+        }
         for stmt in statements {
             self.resolve_stmt(stmt)?;
         }
         Ok(())
     }
 
+    fn error(&mut self, line: usize, message: &str) {
+        self.interpreter.reporter.error(line, message);
+        self.had_error = true;
+    }
+
     fn resolve_stmt(&mut self, stmt: &Stmt) -> Result<(), ParseError> {
         stmt.accept(self)?;
         Ok(())
@@ -58,27 +116,64 @@ impl Resolver<'_> {
 
     fn begin_scope(&mut self) {
         self.scopes.push(HashMap::new());
+        self.arities.push(HashMap::new());
     }
 
     fn end_scope(&mut self) {
         self.scopes.pop();
+        self.arities.pop();
     }
 
-    fn declare(&mut self, name: &Token) -> Result<(), ParseError> {
-        if !self.scopes.is_empty() {
-            if self.scopes.last().unwrap().contains_key(&name.lexeme) {
-                crate::error(
-                    name.line,
-                    "Already a variable with this name in this scope.",
-                );
-                return Err(ParseError {});
-            }
-            self.scopes
-                .last_mut()
-                .unwrap()
-                .insert(name.lexeme.clone(), false);
+    /// Index of the innermost scope declaring `name`, `None` for globals
+    fn scope_index(&self, name: &Token) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rposition(|scope| scope.contains_key(&name.lexeme))
+    }
+
+    /// Arities of the functions visible from the scope `name` resolves to
+    fn arities_for(&mut self, name: &Token) -> &mut HashMap<String, (usize, Option<usize>)> {
+        let index = self.scope_index(name).map_or(0, |i| i + 1);
+        &mut self.arities[index]
+    }
+
+    fn declare(&mut self, name: &Token) {
+        let already_declared = if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), false).is_some()
+        } else {
+            !self.globals.insert(name.lexeme.clone())
+        };
+
+        // Whatever this name was before, it isn't that function anymore
+        self.arities.last_mut().unwrap().remove(&name.lexeme);
+
+        if already_declared {
+            self.error(
+                name.line,
+                &format!("Name {} already declared in this scope.", name.lexeme),
+            );
+        }
+    }
+
+    /// Warns if `warn_shadowing` is set and a local `name` hides one of an enclosing scope
+    fn check_shadowing(&self, name: &Token) {
+        let Some((_, enclosing)) = self.scopes.split_last() else {
+            return;
+        };
+        if self.warn_shadowing
+            && (enclosing
+                .iter()
+                .any(|scope| scope.contains_key(&name.lexeme))
+                || self.globals.contains(&name.lexeme))
+        {
+            self.interpreter.reporter.warn(
+                name.line,
+                &format!(
+                    "Variable {} shadows a variable of an enclosing scope.",
+                    name.lexeme
+                ),
+            );
         }
-        Ok(())
     }
 
     fn define(&mut self, name: &Token) {
@@ -90,11 +185,43 @@ impl Resolver<'_> {
         }
     }
 
-    fn resolve_local(&mut self, expr: &Expr, name: &Token) {
-        for (i, scope) in self.scopes.iter().enumerate().rev() {
-            if scope.contains_key(&name.lexeme) {
-                self.interpreter.resolve(expr, self.scopes.len() - 1 - i);
-            }
+    /// Name of a loop's label, which can't be one of an enclosing loop so
+    /// `break label;` is never ambiguous
+    fn loop_label(&mut self, label: Option<&Token>) -> Option<String> {
+        let label = label?;
+        if self.loops.contains(&Some(label.lexeme.clone())) {
+            self.error(
+                label.line,
+                &format!(
+                    "Label '{}' is already used by an enclosing loop.",
+                    label.lexeme
+                ),
+            );
+        }
+        Some(label.lexeme.clone())
+    }
+
+    /// Resolves the body of a `for (name in ...)` loop in a scope holding the loop variable
+    fn resolve_for_body(
+        &mut self,
+        label: Option<&Token>,
+        name: &Token,
+        body: &Stmt,
+    ) -> Result<(), ParseError> {
+        let label = self.loop_label(label);
+        self.begin_scope();
+        self.declare(name);
+        self.define(name);
+        self.loops.push(label);
+        let result = self.resolve_stmt(body);
+        self.loops.pop();
+        self.end_scope();
+        result
+    }
+
+    fn resolve_local(&mut self, uuid: usize, name: &Token) {
+        if let Some(i) = self.scope_index(name) {
+            self.interpreter.resolve(uuid, self.scopes.len() - 1 - i);
         }
     }
 
@@ -104,15 +231,25 @@ impl Resolver<'_> {
         function_type: FunctionType,
     ) -> Result<(), ParseError> {
         let enclosing_fn = self.current_function;
+        let enclosing_loops = std::mem::take(&mut self.loops);
         self.current_function = function_type;
         self.begin_scope();
-        for param in function.params.iter() {
-            self.declare(param)?;
+        for (param, default) in function.params.iter() {
+            // Defaults are evaluated in the function's scope and can use the parameters before
+            if let Some(default) = default {
+                self.resolve_expr(default)?;
+            }
+            self.declare(param);
             self.define(param);
         }
-        self.resolve_statements(&function.body)?;
+        if let Some(rest) = &function.rest {
+            self.declare(rest);
+            self.define(rest);
+        }
+        self.resolve_body(&function.body)?;
         self.end_scope();
         self.current_function = enclosing_fn;
+        self.loops = enclosing_loops;
         Ok(())
     }
 }
@@ -122,19 +259,20 @@ impl expr::Visitor<Result<(), ParseError>> for Resolver<'_> {
         if !self.scopes.is_empty()
             && self.scopes.last().unwrap().get(&expr.name.lexeme) == Some(&false)
         {
-            crate::error(
+            self.error(
                 expr.name.line,
                 "Can't read local variable in its own initializer.",
             );
-            return Err(ParseError {});
         }
-        self.resolve_local(&Expr::Variable(expr.clone()), &expr.name);
+        self.resolve_local(expr.uuid, &expr.name);
         Ok(())
     }
 
     fn visit_assignment(&mut self, expr: &expr::Assignment) -> Result<(), ParseError> {
         self.resolve_expr(&expr.value)?;
-        self.resolve_local(&Expr::Assignment(expr.clone()), &expr.name);
+        self.resolve_local(expr.uuid, &expr.name);
+        // Could be anything now, so leave checking calls to the interpreter
+        self.assigned.insert(expr.name.lexeme.clone());
         Ok(())
     }
 
@@ -146,6 +284,18 @@ impl expr::Visitor<Result<(), ParseError>> for Resolver<'_> {
 
     fn visit_call(&mut self, expr: &expr::Call) -> Result<(), ParseError> {
         self.resolve_expr(&expr.callee)?;
+        // Direct calls of functions declared in scope can be checked here already,
+        // everything else is checked when calling at runtime
+        if let Expr::Variable(callee) = expr.callee.as_ref()
+            && let Some(&(min, max)) = self.arities_for(&callee.name).get(&callee.name.lexeme)
+            && (expr.arguments.len() < min || max.is_some_and(|max| expr.arguments.len() > max))
+        {
+            self.arity_errors.push((
+                expr.paren.line,
+                callee.name.lexeme.clone(),
+                arity_mismatch(min, max, expr.arguments.len()),
+            ));
+        }
         for arg in &expr.arguments {
             self.resolve_expr(arg)?;
         }
@@ -157,6 +307,34 @@ impl expr::Visitor<Result<(), ParseError>> for Resolver<'_> {
         Ok(())
     }
 
+    fn visit_index(&mut self, expr: &expr::Index) -> Result<(), ParseError> {
+        self.resolve_expr(&expr.object)?;
+        self.resolve_expr(&expr.index)?;
+        Ok(())
+    }
+
+    fn visit_slice(&mut self, expr: &expr::Slice) -> Result<(), ParseError> {
+        self.resolve_expr(&expr.object)?;
+        for bound in [&expr.start, &expr.end].into_iter().flatten() {
+            self.resolve_expr(bound)?;
+        }
+        Ok(())
+    }
+
+    fn visit_interpolation(&mut self, expr: &expr::Interpolation) -> Result<(), ParseError> {
+        for part in &expr.parts {
+            self.resolve_expr(part)?;
+        }
+        Ok(())
+    }
+
+    fn visit_list(&mut self, expr: &expr::List) -> Result<(), ParseError> {
+        for element in &expr.elements {
+            self.resolve_expr(element)?;
+        }
+        Ok(())
+    }
+
     fn visit_literal(&self, _: &expr::Literal) -> Result<(), ParseError> {
         Ok(())
     }
@@ -172,6 +350,14 @@ impl expr::Visitor<Result<(), ParseError>> for Resolver<'_> {
         Ok(())
     }
 
+    fn visit_optional_chain(&mut self, expr: &expr::OptionalChain) -> Result<(), ParseError> {
+        self.resolve_expr(&expr.expr)
+    }
+
+    fn visit_unwrap(&mut self, expr: &expr::Unwrap) -> Result<(), ParseError> {
+        self.resolve_expr(&expr.expr)
+    }
+
     fn visit_get(&mut self, expr: &expr::Get) -> Result<(), ParseError> {
         self.resolve_expr(&expr.object)?;
         Ok(())
@@ -183,40 +369,57 @@ impl expr::Visitor<Result<(), ParseError>> for Resolver<'_> {
         Ok(())
     }
 
+    fn visit_set_index(&mut self, expr: &expr::SetIndex) -> Result<(), ParseError> {
+        self.resolve_expr(&expr.value)?;
+        self.resolve_expr(&expr.object)?;
+        self.resolve_expr(&expr.index)?;
+        Ok(())
+    }
+
     fn visit_self_expr(&mut self, expr: &expr::SelfExpr) -> Result<(), ParseError> {
         if let ClassType::None = self.current_class {
-            crate::error(expr.keyword.line, "Can't use 'this' ouside of a class.");
-            return Err(ParseError {});
+            self.error(expr.keyword.line, "Can't use 'this' ouside of a class.");
         }
-        self.resolve_local(&Expr::SelfExpr(expr.clone()), &expr.keyword);
+        self.resolve_local(expr.uuid, &expr.keyword);
         Ok(())
     }
 
     fn visit_super_expr(&mut self, expr: &expr::SuperExpr) -> Result<(), ParseError> {
         match self.current_class {
             ClassType::None => {
-                crate::error(expr.keyword.line, "Can't use 'super' outside of a class.");
-                return Err(ParseError {});
+                self.error(expr.keyword.line, "Can't use 'super' outside of a class.");
             }
             ClassType::SubClass => (),
             _ => {
-                crate::error(
+                self.error(
                     expr.keyword.line,
                     "Can't use 'super' in a class with no superclass.",
                 );
-                return Err(ParseError {});
             }
         }
 
-        self.resolve_local(&Expr::SuperExpr(expr.clone()), &expr.keyword);
+        self.resolve_local(expr.uuid, &expr.keyword);
         Ok(())
     }
 }
 
 impl stmt::Visitor<Result<(), ParseError>> for Resolver<'_> {
+    fn visit_try(&mut self, stmt: &stmt::Try) -> Result<(), ParseError> {
+        self.begin_scope();
+        self.resolve_body(&stmt.body)?;
+        self.end_scope();
+
+        self.begin_scope();
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+        self.resolve_body(&stmt.handler)?;
+        self.end_scope();
+        Ok(())
+    }
+
     fn visit_block(&mut self, stmt: &stmt::Block) -> Result<(), ParseError> {
         self.begin_scope();
-        self.resolve_statements(&stmt.statements)?;
+        self.resolve_body(&stmt.statements)?;
         self.end_scope();
         Ok(())
     }
@@ -224,16 +427,15 @@ impl stmt::Visitor<Result<(), ParseError>> for Resolver<'_> {
     fn visit_class(&mut self, stmt: &stmt::Class) -> Result<(), ParseError> {
         let enclosing_class = self.current_class;
         self.current_class = ClassType::Class;
-        self.declare(&stmt.name)?;
+        self.declare(&stmt.name);
         self.define(&stmt.name);
 
         if let Some(Expr::Variable(sc)) = &stmt.superclass {
             if stmt.name.lexeme.eq(&sc.name.lexeme) {
-                crate::error(sc.name.line, "A class can't inherit from itself.");
-                return Err(ParseError {});
+                self.error(sc.name.line, "A class can't inherit from itself.");
             }
             self.current_class = ClassType::SubClass;
-            self.resolve_expr(&Expr::Variable(sc.clone()));
+            self.resolve_expr(&Expr::Variable(sc.clone()))?;
             self.begin_scope();
             self.scopes
                 .last_mut()
@@ -247,9 +449,24 @@ impl stmt::Visitor<Result<(), ParseError>> for Resolver<'_> {
             .unwrap()
             .insert(String::from("self"), true);
 
+        let initializers = stmt
+            .methods
+            .iter()
+            .filter(|method| matches!(method, Stmt::Function(f) if is_initializer(&f.name.lexeme)))
+            .count();
+        if initializers > 1 {
+            self.error(
+                stmt.name.line,
+                &format!(
+                    "A class can't have both '{}' and '{}' initializers.",
+                    INITIALIZER_NAMES[0], INITIALIZER_NAMES[1]
+                ),
+            );
+        }
+
         for method in stmt.methods.iter() {
             if let Stmt::Function(method) = method {
-                let declaration = if method.name.lexeme == "new" {
+                let declaration = if is_initializer(&method.name.lexeme) {
                     FunctionType::Initializer
                 } else {
                     FunctionType::Method
@@ -284,40 +501,122 @@ impl stmt::Visitor<Result<(), ParseError>> for Resolver<'_> {
         Ok(())
     }
 
+    fn visit_import(&mut self, stmt: &stmt::Import) -> Result<(), ParseError> {
+        self.resolve_body(&stmt.statements)
+    }
+
     fn visit_var(&mut self, stmt: &stmt::Var) -> Result<(), ParseError> {
-        self.declare(&stmt.name)?;
+        self.check_shadowing(&stmt.name);
+        self.declare(&stmt.name);
         self.resolve_expr(&stmt.initializer)?;
         self.define(&stmt.name);
 
         Ok(())
     }
 
+    fn visit_multi_var(&mut self, stmt: &stmt::MultiVar) -> Result<(), ParseError> {
+        for var in &stmt.vars {
+            self.visit_var(var)?;
+        }
+        Ok(())
+    }
+
     fn visit_while(&mut self, stmt: &stmt::While) -> Result<(), ParseError> {
+        if let Expr::Literal(expr::Literal {
+            value: LiteralType::Boolean(true),
+            ..
+        }) = stmt.condition.as_ref()
+            && !can_exit_loop(&stmt.body, &[])
+        {
+            self.interpreter.reporter.warn(
+                stmt.keyword.line,
+                "Infinite loop, the body contains no 'break' or 'return'.",
+            );
+        }
+
+        let label = self.loop_label(stmt.label.as_ref());
+
         self.resolve_expr(&stmt.condition)?;
-        self.resolve_stmt(&stmt.body)?;
+        self.loops.push(label);
+        let result = self.resolve_stmt(&stmt.body);
+        self.loops.pop();
+        result
+    }
+
+    fn visit_for_range(&mut self, stmt: &stmt::ForRange) -> Result<(), ParseError> {
+        self.resolve_expr(&stmt.start)?;
+        self.resolve_expr(&stmt.end)?;
+        if let Some(step) = &stmt.step {
+            self.resolve_expr(step)?;
+        }
+        self.resolve_for_body(stmt.label.as_ref(), &stmt.name, &stmt.body)
+    }
+
+    fn visit_for_each(&mut self, stmt: &stmt::ForEach) -> Result<(), ParseError> {
+        self.resolve_expr(&stmt.iterable)?;
+        self.resolve_for_body(stmt.label.as_ref(), &stmt.name, &stmt.body)
+    }
+
+    fn visit_break(&mut self, stmt: &stmt::Break) -> Result<(), ParseError> {
+        if self.loops.is_empty() {
+            self.error(stmt.keyword.line, "Can't use 'break' outside of a loop.");
+        } else if let Some(label) = &stmt.label
+            && !self.loops.contains(&Some(label.lexeme.clone()))
+        {
+            self.error(
+                label.line,
+                &format!("No enclosing loop is labeled '{}'.", label.lexeme),
+            );
+        }
         Ok(())
     }
 
     fn visit_function(&mut self, stmt: &stmt::Function) -> Result<(), ParseError> {
-        self.declare(&stmt.name)?;
-        self.define(&stmt.name);
-
+        // the name was already declared by `resolve_body`
         self.resolve_function(stmt, FunctionType::Function)?;
         Ok(())
     }
 
     fn visit_return(&mut self, stmt: &stmt::Return) -> Result<(), ParseError> {
-        if let FunctionType::None = self.current_function {
-            crate::error(
+        if self.current_function == FunctionType::None && !self.interpreter.top_level_return {
+            self.error(
                 stmt.keyword.line,
                 "Can't return without enclosing function!",
             );
-            return Err(ParseError {});
         } else if let FunctionType::Initializer = self.current_function {
-            crate::error(stmt.keyword.line, "Can't return from an initializer!");
-            return Err(ParseError {});
+            self.error(stmt.keyword.line, "Can't return from an initializer!");
         }
         self.resolve_expr(&stmt.value)?;
         Ok(())
     }
 }
+
+/// Whether `stmt` contains a `break` or `return` which leaves the loop it's the body of,
+/// `nested` are the labels of the loops in between, `None` for loops without label
+fn can_exit_loop(stmt: &Stmt, nested: &[Option<&str>]) -> bool {
+    let can_exit = |stmt: &Stmt| can_exit_loop(stmt, nested);
+    match stmt {
+        Stmt::Return(_) => true,
+        // labels are unique among enclosing loops, so a labeled `break` leaves
+        // this loop unless it targets one in between
+        Stmt::Break(break_stmt) => match &break_stmt.label {
+            Some(target) => !nested.contains(&Some(target.lexeme.as_str())),
+            None => nested.is_empty(),
+        },
+        Stmt::Block(block) => block.statements.iter().any(can_exit),
+        Stmt::Try(try_stmt) => {
+            try_stmt.body.iter().any(can_exit) || try_stmt.handler.iter().any(can_exit)
+        }
+        Stmt::If(if_stmt) => {
+            can_exit(&if_stmt.then_branch) || if_stmt.else_branch.as_deref().is_some_and(can_exit)
+        }
+        Stmt::While(stmt::While { label, body, .. })
+        | Stmt::ForRange(stmt::ForRange { label, body, .. })
+        | Stmt::ForEach(stmt::ForEach { label, body, .. }) => {
+            let mut nested = nested.to_vec();
+            nested.push(label.as_ref().map(|label| label.lexeme.as_str()));
+            can_exit_loop(body, &nested)
+        }
+        _ => false,
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    error,
+    error_reporter::ErrorReporter,
     token::{LiteralType, Token, TokenType},
 };
 
@@ -9,6 +9,25 @@ pub struct Scanner {
     start: usize,
     current: usize,
     line: usize,
+    line_directives: Vec<LineDirective>,
+    keep_comments: bool,
+    /// Comments waiting for the next token when `keep_comments` is set
+    comments: Vec<String>,
+    reporter: ErrorReporter,
+    /// Number of unclosed `{` in each `${...}` being scanned, the innermost one last
+    interpolations: Vec<usize>,
+    /// Offset and column of the last token, columns are counted from there so long
+    /// lines aren't scanned from their start for every token
+    last_column: (usize, usize),
+}
+
+/// Set by `#line N "file"`, from `line` on lines are reported as if the
+/// source started at line `reported_line` of `file`
+#[derive(Debug, Clone)]
+pub struct LineDirective {
+    pub line: usize,
+    pub reported_line: usize,
+    pub file: Option<String>,
 }
 
 impl Scanner {
@@ -18,10 +37,28 @@ impl Scanner {
             tokens: Vec::new(),
             start: 0,
             current: 0,
-            line: 0,
+            line: 1,
+            line_directives: Vec::new(),
+            keep_comments: false,
+            comments: Vec::new(),
+            reporter: ErrorReporter::default(),
+            interpolations: Vec::new(),
+            last_column: (0, 1),
         }
     }
 
+    /// Report errors to `reporter` instead of printing them to stderr
+    pub fn with_reporter(mut self, reporter: ErrorReporter) -> Self {
+        self.reporter = reporter;
+        self
+    }
+
+    /// Keep comments as `leading_comments` of the token after them instead of dropping them
+    pub fn keep_comments(mut self) -> Self {
+        self.keep_comments = true;
+        self
+    }
+
     fn is_at_end(&self) -> bool {
         self.current >= self.source.len()
     }
@@ -33,7 +70,11 @@ impl Scanner {
     }
 
     fn peek(&self) -> u8 {
-        self.source.as_bytes()[self.current]
+        if self.is_at_end() {
+            0
+        } else {
+            self.source.as_bytes()[self.current]
+        }
     }
 
     fn peek_next(&self) -> u8 {
@@ -49,27 +90,66 @@ impl Scanner {
         match c {
             b'(' => self.add_token(TokenType::LeftParen, LiteralType::Nil),
             b')' => self.add_token(TokenType::RightParen, LiteralType::Nil),
-            b'{' => self.add_token(TokenType::LeftBrace, LiteralType::Nil),
-            b'}' => self.add_token(TokenType::RightBrace, LiteralType::Nil),
+            b'{' => {
+                if let Some(open) = self.interpolations.last_mut() {
+                    *open += 1;
+                }
+                self.add_token(TokenType::LeftBrace, LiteralType::Nil)
+            }
+            b'}' => match self.interpolations.last_mut() {
+                // closes the `${`, scan the rest of the string
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.string(b'"');
+                }
+                Some(open) => {
+                    *open -= 1;
+                    self.add_token(TokenType::RightBrace, LiteralType::Nil)
+                }
+                None => self.add_token(TokenType::RightBrace, LiteralType::Nil),
+            },
+            b'[' => self.add_token(TokenType::LeftBracket, LiteralType::Nil),
+            b']' => self.add_token(TokenType::RightBracket, LiteralType::Nil),
             b',' => self.add_token(TokenType::Comma, LiteralType::Nil),
-            b'.' => self.add_token(TokenType::Dot, LiteralType::Nil),
+            b'.' => {
+                if !self.is_at_end() && self.peek().is_ascii_digit() && !self.follows_operand() {
+                    self.fraction();
+                } else if self.peek() == b'.' && self.peek_next() == b'.' {
+                    self.current += 2;
+                    self.add_token(TokenType::DotDotDot, LiteralType::Nil);
+                } else if self.expect_next(b'.') {
+                    self.add_token(TokenType::DotDot, LiteralType::Nil);
+                } else {
+                    self.add_token(TokenType::Dot, LiteralType::Nil);
+                }
+            }
             b'-' => self.add_token(TokenType::Minus, LiteralType::Nil),
             b'+' => self.add_token(TokenType::Plus, LiteralType::Nil),
             b';' => self.add_token(TokenType::Semicolon, LiteralType::Nil),
+            b':' => self.add_token(TokenType::Colon, LiteralType::Nil),
             b'*' => self.add_token(TokenType::Star, LiteralType::Nil),
             b'%' => self.add_token(TokenType::Percentage, LiteralType::Nil),
             b'&' => {
                 if self.expect_next(b'&') {
                     self.add_token(TokenType::AmperAmper, LiteralType::Nil);
                 } else {
-                    error(self.line, &format!("Unexpected Character {c}"));
+                    self.unexpected_character();
                 }
             }
             b'|' => {
                 if self.expect_next(b'|') {
                     self.add_token(TokenType::BarBar, LiteralType::Nil);
                 } else {
-                    error(self.line, &format!("Unexpected Character {c}"));
+                    self.unexpected_character();
+                }
+            }
+            b'?' => {
+                if self.expect_next(b'?') {
+                    self.add_token(TokenType::QuestionQuestion, LiteralType::Nil);
+                } else if self.expect_next(b'.') {
+                    self.add_token(TokenType::QuestionDot, LiteralType::Nil);
+                } else {
+                    self.unexpected_character();
                 }
             }
             b'=' => {
@@ -105,6 +185,10 @@ impl Scanner {
                     while !self.is_at_end() && (self.peek() != b'\n') {
                         self.advance();
                     }
+                    if self.keep_comments {
+                        let comment = self.source[self.start..self.current].to_string();
+                        self.comments.push(comment);
+                    }
                 } else {
                     self.add_token(TokenType::Slash, LiteralType::Nil);
                 }
@@ -113,6 +197,20 @@ impl Scanner {
             b'"' => self.string(b'"'),
             b'\'' => self.string(b'\''),
 
+            b'#' if self.start == 0 || self.source.as_bytes()[self.start - 1] == b'\n' => {
+                self.line_directive()
+            }
+
+            // `\` at the end of a line continues the line, there are no other uses of `\` yet
+            b'\\'
+                if self.peek() == b'\n' || (self.peek() == b'\r' && self.peek_next() == b'\n') =>
+            {
+                if self.peek() == b'\r' {
+                    self.advance();
+                }
+                self.advance();
+                self.line += 1;
+            }
             b' ' | b'\t' | b'\r' => {}
             b'\n' => self.line += 1,
             _ => {
@@ -121,12 +219,77 @@ impl Scanner {
                 } else if c.is_ascii_alphabetic() {
                     self.identifier();
                 } else {
-                    error(self.line, &format!("Unexpected Character {}", c as char));
+                    self.unexpected_character();
                 }
             }
         }
     }
 
+    /// Reports the character at `start` with its column, consuming all of it
+    /// if it's longer than a byte so each character is only reported once
+    fn unexpected_character(&mut self) {
+        while !self.is_at_end() && !self.source.is_char_boundary(self.current) {
+            self.current += 1;
+        }
+        let column = self.column();
+        let c = &self.source[self.start..self.current];
+        self.reporter.error_at(
+            self.line,
+            Some(column),
+            &format!("Unexpected Character '{c}' at column {column}"),
+        );
+    }
+
+    /// 1-based column of the start of the current token
+    fn column(&mut self) -> usize {
+        let (offset, column) = if self.last_column.0 <= self.start {
+            self.last_column
+        } else {
+            (0, 1)
+        };
+        let skipped = &self.source[offset..self.start];
+        let column = match skipped.rfind('\n') {
+            Some(newline) => skipped[newline + 1..].chars().count() + 1,
+            None => column + skipped.chars().count(),
+        };
+        self.last_column = (self.start, column);
+        column
+    }
+
+    /// `#line N` or `#line N "file"`, for generated code to point errors at the original source
+    fn line_directive(&mut self) {
+        while !self.is_at_end() && self.peek() != b'\n' {
+            self.advance();
+        }
+        let directive = &self.source[self.start..self.current];
+        let mut parts = directive.trim_end().splitn(3, ' ');
+        let reported_line = match (parts.next(), parts.next()) {
+            (Some("#line"), Some(n)) => n.parse().ok(),
+            _ => None,
+        };
+        let file = match parts.next() {
+            Some(file) if file.len() >= 2 && file.starts_with('"') && file.ends_with('"') => {
+                Some(Some(file[1..file.len() - 1].to_string()))
+            }
+            Some(_) => None,
+            // without a file keep the one of the previous directive
+            None => Some(self.line_directives.last().and_then(|d| d.file.clone())),
+        };
+
+        if let (Some(reported_line), Some(file)) = (reported_line, file) {
+            self.line_directives.push(LineDirective {
+                line: self.line + 1,
+                reported_line,
+                file,
+            });
+        } else {
+            self.reporter
+                .error(self.line, "Expected '#line N' or '#line N \"file\"'.");
+        }
+    }
+
+    /// Identifiers start with a letter and continue with letters and digits, so `a1b2` is
+    /// one identifier while `2abc` is scanned by `number` as `2` followed by `abc`
     fn identifier(&mut self) {
         while self.peek().is_ascii_alphanumeric() {
             self.advance();
@@ -137,7 +300,7 @@ impl Scanner {
         if let Some(token_type) = get_keyword(&text) {
             self.add_token(token_type, LiteralType::Nil);
         } else {
-            self.add_token(TokenType::Identifier, LiteralType::String(text));
+            self.add_token(TokenType::Identifier, LiteralType::String(text.into()));
         }
     }
 
@@ -146,11 +309,22 @@ impl Scanner {
             self.advance();
         }
 
+        // A dot not followed by a digit isn't part of the number, so `5.foo` is
+        // scanned as `5`, `.`, `foo` and `5.` at the end as `5`, `.`
         if self.peek() == b'.' && self.peek_next().is_ascii_digit() {
             self.advance();
             while self.peek().is_ascii_digit() && !self.is_at_end() {
                 self.advance();
             }
+        } else {
+            #[cfg(feature = "bigint")]
+            if self.peek() == b'n' {
+                let digits = &self.source[self.start..self.current];
+                let value = LiteralType::BigInt(digits.parse().unwrap());
+                self.advance();
+                self.add_token(TokenType::Number, value);
+                return;
+            }
         }
 
         let text = String::from(&self.source[self.start..self.current]);
@@ -160,26 +334,161 @@ impl Scanner {
         );
     }
 
+    /// Scans a number with a leading dot like `.5`, the dot is already consumed
+    fn fraction(&mut self) {
+        while !self.is_at_end() && self.peek().is_ascii_digit() {
+            self.advance();
+        }
+
+        let text = format!("0{}", &self.source[self.start..self.current]);
+        self.add_token(
+            TokenType::Number,
+            LiteralType::Number(text.parse::<f64>().unwrap()),
+        );
+    }
+
+    /// Whether the previous token can be followed by a `.` for property access
+    fn follows_operand(&self) -> bool {
+        self.tokens.last().is_some_and(|token| {
+            matches!(
+                token.token_type,
+                TokenType::Identifier
+                    | TokenType::RightParen
+                    | TokenType::RightBracket
+                    | TokenType::SelfKW
+            )
+        })
+    }
+
+    /// Scans a string until `delimiter`, `${` in a `"` string ends the fragment before it as
+    /// an `Interpolation` token and the expression after it is scanned as usual
     fn string(&mut self, delimiter: u8) {
-        while self.peek() != delimiter && !self.is_at_end() {
-            if self.peek() == b'\n' {
-                self.line += 1;
+        let mut text = String::new();
+        let mut segment_start = self.current;
+        while !self.is_at_end() && self.peek() != delimiter {
+            match self.peek() {
+                b'\n' => self.line += 1,
+                // normalize `\r\n` to `\n`, the line is counted when reaching the `\n`
+                b'\r' if self.peek_next() == b'\n' => {
+                    text.push_str(&self.source[segment_start..self.current]);
+                    self.advance();
+                    segment_start = self.current;
+                    continue;
+                }
+                b'\\' => {
+                    text.push_str(&self.source[segment_start..self.current]);
+                    self.advance();
+                    if let Some(c) = self.escape_sequence() {
+                        text.push(c);
+                    }
+                    segment_start = self.current;
+                    continue;
+                }
+                b'$' if delimiter == b'"' && self.peek_next() == b'{' => {
+                    text.push_str(&self.source[segment_start..self.current]);
+                    self.current += 2;
+                    self.add_token(TokenType::Interpolation, LiteralType::String(text.into()));
+                    self.interpolations.push(0);
+                    return;
+                }
+                _ => {}
             }
             self.advance();
         }
 
         if self.is_at_end() {
-            error(
+            self.reporter.error(
                 self.line,
                 &format!("Unterminated string (missing {}", delimiter as char),
             );
+            return;
         }
 
+        text.push_str(&self.source[segment_start..self.current]);
+
         // consume closing delimiter
         self.advance();
 
-        let text = String::from(&self.source[self.start + 1..self.current - 1]);
-        self.add_token(TokenType::String, LiteralType::String(text));
+        self.add_token(TokenType::String, LiteralType::String(text.into()));
+    }
+
+    /// Parses the escape sequence after a `\` in a string
+    fn escape_sequence(&mut self) -> Option<char> {
+        if self.is_at_end() {
+            return None;
+        }
+        match self.advance() {
+            b'n' => Some('\n'),
+            b't' => Some('\t'),
+            b'r' => Some('\r'),
+            b'0' => Some('\0'),
+            b'\\' => Some('\\'),
+            b'"' => Some('"'),
+            b'\'' => Some('\''),
+            b'$' => Some('$'),
+            // `\` at the end of a line joins it with the next one without a newline
+            b'\r' if self.peek() == b'\n' => {
+                self.advance();
+                self.line += 1;
+                None
+            }
+            b'\n' => {
+                self.line += 1;
+                None
+            }
+            b'x' => {
+                let start = self.current;
+                while self.current - start < 2
+                    && !self.is_at_end()
+                    && self.peek().is_ascii_hexdigit()
+                {
+                    self.advance();
+                }
+                if self.current - start != 2 {
+                    self.reporter
+                        .error(self.line, "Expected two hex digits after '\\x'");
+                    return None;
+                }
+                self.code_point(start, self.current)
+            }
+            b'u' => {
+                if !self.expect_next(b'{') {
+                    self.reporter.error(self.line, "Expected '{' after '\\u'");
+                    return None;
+                }
+                let start = self.current;
+                while !self.is_at_end() && self.peek().is_ascii_hexdigit() {
+                    self.advance();
+                }
+                let end = self.current;
+                if !self.expect_next(b'}') || end == start || end - start > 6 {
+                    self.reporter
+                        .error(self.line, "Expected one to six hex digits in '\\u{...}'");
+                    return None;
+                }
+                self.code_point(start, end)
+            }
+            c => {
+                self.reporter.error(
+                    self.line,
+                    &format!("Unknown escape sequence \\{}", c as char),
+                );
+                None
+            }
+        }
+    }
+
+    /// Turns the hex digits between `start` and `end` into a char
+    fn code_point(&self, start: usize, end: usize) -> Option<char> {
+        let digits = &self.source[start..end];
+        let c = u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32);
+        if c.is_none() {
+            self.reporter
+                .error(self.line, &format!("Invalid code point {digits}"));
+        }
+        c
     }
 
     fn expect_next(&mut self, expected: u8) -> bool {
@@ -196,41 +505,57 @@ impl Scanner {
 
     fn add_token(&mut self, token_type: TokenType, literal: LiteralType) {
         let text = String::from(&self.source[self.start..self.current]);
-        self.tokens
-            .push(Token::new(token_type, text, literal, self.line));
+        let mut token = Token::new(token_type, text, literal, self.line);
+        token.column = self.column();
+        token.leading_comments = std::mem::take(&mut self.comments);
+        self.tokens.push(token);
     }
 
-    pub fn scan_tokens(&mut self) -> Vec<Token> {
+    /// Scans the whole source, consuming the scanner so the tokens can be moved out
+    pub fn scan_tokens(self) -> Vec<Token> {
+        self.scan().0
+    }
+
+    /// Like `scan_tokens`, but also returns the `#line` directives found on the way
+    pub fn scan(mut self) -> (Vec<Token>, Vec<LineDirective>) {
         while !self.is_at_end() {
             self.start = self.current;
             self.scan_token();
         }
 
-        self.tokens.push(Token::new(
-            TokenType::Eof,
-            String::new(),
-            LiteralType::Nil,
-            self.line,
-        ));
-        self.tokens.clone()
+        if !self.interpolations.is_empty() {
+            self.reporter
+                .error(self.line, "Unterminated string interpolation (missing })");
+        }
+
+        self.start = self.current;
+        self.add_token(TokenType::Eof, LiteralType::Nil);
+        (self.tokens, self.line_directives)
     }
 }
 
 fn get_keyword(word: &str) -> Option<TokenType> {
     match word {
         "and" => Some(TokenType::And),
+        "break" => Some(TokenType::Break),
+        "catch" => Some(TokenType::Catch),
         "class" => Some(TokenType::Class),
+        "div" => Some(TokenType::Div),
+        "elif" => Some(TokenType::Elif),
         "else" => Some(TokenType::Else),
         "false" => Some(TokenType::False),
         "fun" => Some(TokenType::Fun),
         "for" => Some(TokenType::For),
         "if" => Some(TokenType::If),
+        "import" => Some(TokenType::Import),
+        "in" => Some(TokenType::In),
         "nil" => Some(TokenType::Nil),
         "or" => Some(TokenType::Or),
         "return" => Some(TokenType::Return),
         "super" => Some(TokenType::SuperKW),
         "self" => Some(TokenType::SelfKW),
         "true" => Some(TokenType::True),
+        "try" => Some(TokenType::Try),
         "var" => Some(TokenType::Var),
         "while" => Some(TokenType::While),
         _ => None,
@@ -0,0 +1,697 @@
+use crate::{
+    environment::Environment,
+    error_reporter::ErrorReporter,
+    interpreter::{Exit, Interpreter, comparison_error},
+    lox_callable::{Callable, LoxClass, LoxInstance, NativeFunction},
+    token::{LiteralType, Token},
+};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Defines all std functions
+pub fn define_globals(globals: &mut Environment) {
+    define_core_functions(globals);
+    define_list_functions(globals);
+    define_math_functions(globals);
+    define_instance_functions(globals);
+    define_debug_functions(globals);
+}
+
+type NativeFn = fn(&mut Interpreter, &[LiteralType], &Token) -> Result<LiteralType, Exit>;
+
+fn define_native(globals: &mut Environment, name: &str, arity: usize, callable: NativeFn) {
+    define_native_range(globals, name, arity..=arity, callable);
+}
+
+/// Defines a native with optional arguments, use `usize::MAX` as end for variadics
+fn define_native_range(
+    globals: &mut Environment,
+    name: &str,
+    arity: RangeInclusive<usize>,
+    callable: NativeFn,
+) {
+    globals.define(
+        name.to_string(),
+        LiteralType::NativeFunction(NativeFunction {
+            arity,
+            callable,
+            receiver: None,
+        }),
+    );
+}
+
+/// Reports an argument that isn't `expected`, e.g. "a number", every `expect_` helper uses this
+/// so wrong argument types are reported the same way by all natives
+fn type_error(expected: &str, arg: &LiteralType, paren: &Token, reporter: &ErrorReporter) -> Exit {
+    reporter.error(
+        paren.line,
+        &format!("Expected {expected}, got {}.", arg.to_debug_string()),
+    );
+    Exit::RuntimeError
+}
+
+/// Gets the elements of a list argument, erroring for everything else
+fn expect_list(
+    arg: &LiteralType,
+    paren: &Token,
+    reporter: &ErrorReporter,
+) -> Result<Vec<LiteralType>, Exit> {
+    match arg {
+        LiteralType::List(list) => Ok(list.borrow().clone()),
+        _ => Err(type_error("a list", arg, paren, reporter)),
+    }
+}
+
+/// Like `expect_list`, but returns the list itself instead of copying its elements
+fn expect_list_ref<'a>(
+    arg: &'a LiteralType,
+    paren: &Token,
+    reporter: &ErrorReporter,
+) -> Result<&'a Rc<RefCell<Vec<LiteralType>>>, Exit> {
+    match arg {
+        LiteralType::List(list) => Ok(list),
+        _ => Err(type_error("a list", arg, paren, reporter)),
+    }
+}
+
+fn expect_number(arg: &LiteralType, paren: &Token, reporter: &ErrorReporter) -> Result<f64, Exit> {
+    match arg {
+        LiteralType::Number(n) => Ok(*n),
+        _ => Err(type_error("a number", arg, paren, reporter)),
+    }
+}
+
+fn expect_string<'a>(
+    arg: &'a LiteralType,
+    paren: &Token,
+    reporter: &ErrorReporter,
+) -> Result<&'a str, Exit> {
+    match arg {
+        LiteralType::String(s) => Ok(s),
+        _ => Err(type_error("a string", arg, paren, reporter)),
+    }
+}
+
+fn expect_instance(
+    arg: &LiteralType,
+    paren: &Token,
+    reporter: &ErrorReporter,
+) -> Result<Rc<RefCell<LoxInstance>>, Exit> {
+    match arg {
+        LiteralType::Callable(Callable::Instance(instance)) => Ok(Rc::clone(instance)),
+        _ => Err(type_error("an instance", arg, paren, reporter)),
+    }
+}
+
+fn expect_class(
+    arg: &LiteralType,
+    paren: &Token,
+    reporter: &ErrorReporter,
+) -> Result<Rc<LoxClass>, Exit> {
+    match arg {
+        LiteralType::Callable(Callable::Class(class)) => Ok(Rc::clone(class)),
+        _ => Err(type_error("a class", arg, paren, reporter)),
+    }
+}
+
+/// Numbers of `min(a, b)` or `min(list)`
+fn numbers_to_compare(
+    args: &[LiteralType],
+    paren: &Token,
+    reporter: &ErrorReporter,
+) -> Result<Vec<f64>, Exit> {
+    let numbers = match args {
+        [list] => expect_list(list, paren, reporter)?,
+        _ => args.to_vec(),
+    };
+    if numbers.is_empty() {
+        reporter.error(
+            paren.line,
+            "Expected at least one number, got an empty list.",
+        );
+        return Err(Exit::RuntimeError);
+    }
+    numbers
+        .iter()
+        .map(|n| expect_number(n, paren, reporter))
+        .collect()
+}
+
+fn new_list(elements: Vec<LiteralType>) -> LiteralType {
+    LiteralType::List(Rc::new(RefCell::new(elements)))
+}
+
+/// Natural order of numbers and strings, other values can't be compared, `operator` is
+/// how errors describe the comparison
+fn compare(
+    a: &LiteralType,
+    operator: &str,
+    b: &LiteralType,
+    paren: &Token,
+    reporter: &ErrorReporter,
+) -> Result<Ordering, Exit> {
+    let ordering = match (a, b) {
+        (LiteralType::Number(a), LiteralType::Number(b)) => a.partial_cmp(b),
+        (LiteralType::String(a), LiteralType::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+    ordering.ok_or_else(|| comparison_error(paren.line, operator, a, b, reporter))
+}
+
+/// Stable merge sort which, unlike `slice::sort_by`, doesn't panic when `cmp` isn't a total
+/// order and stops at the first error of `cmp`
+fn merge_sort(
+    mut elements: Vec<LiteralType>,
+    cmp: &mut impl FnMut(&LiteralType, &LiteralType) -> Result<Ordering, Exit>,
+) -> Result<Vec<LiteralType>, Exit> {
+    if elements.len() <= 1 {
+        return Ok(elements);
+    }
+    let right = elements.split_off(elements.len() / 2);
+    let left = merge_sort(elements, cmp)?;
+    let right = merge_sort(right, cmp)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        if cmp(a, b)? == Ordering::Greater {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+/// Errors naming the first two elements that `compare` can't order, like a number and a string
+fn check_sortable(
+    elements: &[LiteralType],
+    paren: &Token,
+    reporter: &ErrorReporter,
+) -> Result<(), Exit> {
+    let Some(first) = elements.first() else {
+        return Ok(());
+    };
+    let same_kind = |element: &LiteralType| {
+        matches!(
+            (first, element),
+            (LiteralType::Number(_), LiteralType::Number(_))
+                | (LiteralType::String(_), LiteralType::String(_))
+        )
+    };
+    if !same_kind(first) {
+        reporter.error(
+            paren.line,
+            &format!(
+                "Can't sort {} at index 0, only numbers or strings can be sorted without a comparator.",
+                first.to_debug_string()
+            ),
+        );
+        return Err(Exit::RuntimeError);
+    }
+    if let Some((i, other)) = elements.iter().enumerate().find(|(_, e)| !same_kind(e)) {
+        reporter.error(
+            paren.line,
+            &format!(
+                "Can't sort {} at index 0 and {} at index {i} together, without a comparator the list must only contain numbers or only strings.",
+                first.to_debug_string(),
+                other.to_debug_string()
+            ),
+        );
+        return Err(Exit::RuntimeError);
+    }
+    Ok(())
+}
+
+fn define_core_functions(globals: &mut Environment) {
+    define_native(globals, "clock", 0, |_, _, _| {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        Ok(LiteralType::Number(timestamp.as_millis() as f64))
+    });
+
+    define_native(globals, "print", 1, |_, args, _| {
+        println!("{}", args[0]);
+        Ok(LiteralType::Nil)
+    });
+
+    // eprint(value) is print for stderr, e.g. for diagnostics when stdout is piped elsewhere
+    define_native(globals, "eprint", 1, |_, args, _| {
+        eprintln!("{}", args[0]);
+        Ok(LiteralType::Nil)
+    });
+
+    // throw(value) unwinds to the closest `try` and binds value in its `catch`
+    define_native(globals, "throw", 1, |_, args, _| {
+        Err(Exit::Throw(args[0].clone()))
+    });
+
+    // format(template, args...) replaces each `{}` with the next argument,
+    // `{{` and `}}` are literal braces
+    define_native_range(
+        globals,
+        "format",
+        1..=usize::MAX,
+        |interpreter, args, paren| {
+            let template = expect_string(&args[0], paren, &interpreter.reporter)?;
+            let arguments = &args[1..];
+            let mut formatted = String::new();
+            let mut used = 0;
+            let mut chars = template.chars().peekable();
+            while let Some(c) = chars.next() {
+                match (c, chars.peek()) {
+                    ('{', Some('{')) | ('}', Some('}')) => {
+                        chars.next();
+                        formatted.push(c);
+                    }
+                    ('{', Some('}')) => {
+                        chars.next();
+                        if let Some(argument) = arguments.get(used) {
+                            formatted.push_str(&argument.to_string());
+                        }
+                        used += 1;
+                    }
+                    ('{', _) | ('}', _) => {
+                        interpreter.reporter.error(
+                            paren.line,
+                            &format!(
+                                "Unmatched '{c}' in template, use '{c}{c}' for a literal one."
+                            ),
+                        );
+                        return Err(Exit::RuntimeError);
+                    }
+                    _ => formatted.push(c),
+                }
+            }
+            if used != arguments.len() {
+                interpreter.reporter.error(
+                    paren.line,
+                    &format!(
+                        "Template has {} placeholders but got {} arguments.",
+                        used,
+                        arguments.len()
+                    ),
+                );
+                return Err(Exit::RuntimeError);
+            }
+            Ok(LiteralType::String(formatted.into()))
+        },
+    );
+}
+
+fn define_list_functions(globals: &mut Environment) {
+    // map(list, fn) calls fn on each element and collects the results
+    define_native(globals, "map", 2, |interpreter, args, paren| {
+        let mut mapped = Vec::new();
+        for element in expect_list(&args[0], paren, &interpreter.reporter)? {
+            mapped.push(interpreter.call_value(&args[1], &[element], paren)?);
+        }
+        Ok(new_list(mapped))
+    });
+
+    // filter(list, pred) keeps the elements pred returns something truthy for
+    define_native(globals, "filter", 2, |interpreter, args, paren| {
+        let mut filtered = Vec::new();
+        for element in expect_list(&args[0], paren, &interpreter.reporter)? {
+            let keep = interpreter.call_value(&args[1], std::slice::from_ref(&element), paren)?;
+            if interpreter.is_truthy(&keep) {
+                filtered.push(element);
+            }
+        }
+        Ok(new_list(filtered))
+    });
+
+    // reduce(list, fn, init) folds the list into fn(fn(init, first), second)...
+    define_native(globals, "reduce", 3, |interpreter, args, paren| {
+        let mut accumulator = args[2].clone();
+        for element in expect_list(&args[0], paren, &interpreter.reporter)? {
+            accumulator = interpreter.call_value(&args[1], &[accumulator, element], paren)?;
+        }
+        Ok(accumulator)
+    });
+
+    // sort(list) sorts numbers or strings in place, sort(list, cmp) uses cmp(a, b)
+    // which has to return a negative number, zero or a positive number
+    define_native_range(globals, "sort", 1..=2, |interpreter, args, paren| {
+        let elements = expect_list(&args[0], paren, &interpreter.reporter)?;
+        if args.len() == 1 {
+            check_sortable(&elements, paren, &interpreter.reporter)?;
+        }
+        let elements = merge_sort(elements, &mut |a, b| match args.get(1) {
+            Some(cmp) => match interpreter.call_value(cmp, &[a.clone(), b.clone()], paren) {
+                Ok(LiteralType::Number(n)) => Ok(n.partial_cmp(&0.0).unwrap_or(Ordering::Equal)),
+                Ok(other) => {
+                    interpreter.reporter.error(
+                        paren.line,
+                        &format!(
+                            "Comparator must return a number, got {}.",
+                            other.to_debug_string()
+                        ),
+                    );
+                    Err(Exit::RuntimeError)
+                }
+                Err(e) => Err(e),
+            },
+            None => compare(a, "<", b, paren, &interpreter.reporter),
+        })?;
+
+        if let LiteralType::List(list) = &args[0] {
+            *list.borrow_mut() = elements;
+        }
+        Ok(args[0].clone())
+    });
+
+    // copyList(list) creates a shallow copy: it has the same elements, but changing it
+    // doesn't change the original
+    define_native(globals, "copyList", 1, |interpreter, args, paren| {
+        Ok(new_list(expect_list(
+            &args[0],
+            paren,
+            &interpreter.reporter,
+        )?))
+    });
+
+    // enumerate(list) pairs each element with its index like [[0, first], [1, second]]
+    define_native(globals, "enumerate", 1, |interpreter, args, paren| {
+        let pairs = expect_list(&args[0], paren, &interpreter.reporter)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, element)| new_list(vec![LiteralType::Number(i as f64), element]))
+            .collect();
+        Ok(new_list(pairs))
+    });
+
+    // zip(a, b) pairs up the elements of both lists until the shorter one ends
+    define_native(globals, "zip", 2, |interpreter, args, paren| {
+        let pairs = expect_list(&args[0], paren, &interpreter.reporter)?
+            .into_iter()
+            .zip(expect_list(&args[1], paren, &interpreter.reporter)?)
+            .map(|(a, b)| new_list(vec![a, b]))
+            .collect();
+        Ok(new_list(pairs))
+    });
+
+    // contains(list, value) is whether an element is equal to value like with `==`, except
+    // that `equals` methods aren't called so instances are only equal to themselves
+    define_native(globals, "contains", 2, |interpreter, args, paren| {
+        let list = expect_list_ref(&args[0], paren, &interpreter.reporter)?;
+        let found = list
+            .borrow()
+            .iter()
+            .any(|element| interpreter.is_equal(element, &args[1]));
+        Ok(LiteralType::Boolean(found))
+    });
+
+    // remove(list, index) takes the element at index out of the list and returns it,
+    // negative indices count from the end like for `list[index]`
+    define_native(globals, "remove", 2, |interpreter, args, paren| {
+        let list = expect_list_ref(&args[0], paren, &interpreter.reporter)?;
+        let len = list.borrow().len();
+        let index = interpreter.list_index(len, &args[1], paren)?;
+        let removed = list.borrow_mut().remove(index);
+        Ok(removed)
+    });
+}
+
+fn define_math_functions(globals: &mut Environment) {
+    // min(a, b) or min(list)
+    define_native_range(globals, "min", 1..=2, |interpreter, args, paren| {
+        let numbers = numbers_to_compare(args, paren, &interpreter.reporter)?;
+        Ok(LiteralType::Number(
+            numbers.into_iter().reduce(f64::min).unwrap(),
+        ))
+    });
+
+    // max(a, b) or max(list)
+    define_native_range(globals, "max", 1..=2, |interpreter, args, paren| {
+        let numbers = numbers_to_compare(args, paren, &interpreter.reporter)?;
+        Ok(LiteralType::Number(
+            numbers.into_iter().reduce(f64::max).unwrap(),
+        ))
+    });
+
+    // clamp(x, lo, hi) limits x to lo..=hi
+    define_native(globals, "clamp", 3, |interpreter, args, paren| {
+        let x = expect_number(&args[0], paren, &interpreter.reporter)?;
+        let lo = expect_number(&args[1], paren, &interpreter.reporter)?;
+        let hi = expect_number(&args[2], paren, &interpreter.reporter)?;
+        // `f64::clamp` panics on these
+        if lo.is_nan() || hi.is_nan() {
+            interpreter.reporter.error(
+                paren.line,
+                &format!("Bounds of clamp can't be NaN, got {lo} and {hi}."),
+            );
+            return Err(Exit::RuntimeError);
+        }
+        if lo > hi {
+            interpreter.reporter.error(
+                paren.line,
+                &format!("Lower bound {lo} of clamp is greater than upper bound {hi}."),
+            );
+            return Err(Exit::RuntimeError);
+        }
+        Ok(LiteralType::Number(x.clamp(lo, hi)))
+    });
+
+    // between(x, lo, hi) is whether lo <= x <= hi, for numbers or strings
+    define_native(globals, "between", 3, |interpreter, args, paren| {
+        let reporter = &interpreter.reporter;
+        let above_lo = compare(&args[1], "<=", &args[0], paren, reporter)?.is_le();
+        let below_hi = compare(&args[0], "<=", &args[2], paren, reporter)?.is_le();
+        Ok(LiteralType::Boolean(above_lo && below_hi))
+    });
+
+    // round(x) rounds to the nearest integer, round(x, digits) to that many decimal places
+    define_native_range(globals, "round", 1..=2, |interpreter, args, paren| {
+        let x = expect_number(&args[0], paren, &interpreter.reporter)?;
+        let Some(digits) = args.get(1) else {
+            return Ok(LiteralType::Number(x.round()));
+        };
+        let digits = expect_number(digits, paren, &interpreter.reporter)?;
+        if digits < 0.0 || digits.fract() != 0.0 {
+            interpreter.reporter.error(
+                paren.line,
+                &format!("Digits must be a non-negative integer, got {digits}."),
+            );
+            return Err(Exit::RuntimeError);
+        }
+        // more digits than 10^308 has can't make a difference, and don't fit an i32
+        let factor = 10f64.powi(digits.min(f64::MAX_10_EXP as f64) as i32);
+        let scaled = x * factor;
+        if !factor.is_finite() || !scaled.is_finite() {
+            // x has no digits that far after the point, or is infinite or NaN itself
+            return Ok(LiteralType::Number(x));
+        }
+        Ok(LiteralType::Number(scaled.round() / factor))
+    });
+
+    define_native(globals, "abs", 1, |interpreter, args, paren| {
+        let x = expect_number(&args[0], paren, &interpreter.reporter)?;
+        Ok(LiteralType::Number(x.abs()))
+    });
+
+    // pow(base, exponent) raises base to the power of exponent, exactly if one is a bigint
+    define_native(globals, "pow", 2, |interpreter, args, paren| {
+        #[cfg(feature = "bigint")]
+        if let Some(result) = crate::bigint::pow(&args[0], &args[1], paren, &interpreter.reporter) {
+            return result;
+        }
+        let base = expect_number(&args[0], paren, &interpreter.reporter)?;
+        let exponent = expect_number(&args[1], paren, &interpreter.reporter)?;
+        Ok(LiteralType::Number(base.powf(exponent)))
+    });
+
+    // sign(x) is -1, 0 or 1, and NaN for NaN
+    define_native(globals, "sign", 1, |interpreter, args, paren| {
+        let x = expect_number(&args[0], paren, &interpreter.reporter)?;
+        // `f64::signum` is 1 for 0
+        let sign = if x == 0.0 { 0.0 } else { x.signum() };
+        Ok(LiteralType::Number(sign))
+    });
+
+    // division by zero isn't an error, these check for its results
+    define_native(globals, "isNan", 1, |interpreter, args, paren| {
+        let x = expect_number(&args[0], paren, &interpreter.reporter)?;
+        Ok(LiteralType::Boolean(x.is_nan()))
+    });
+
+    define_native(globals, "isInfinite", 1, |interpreter, args, paren| {
+        let x = expect_number(&args[0], paren, &interpreter.reporter)?;
+        Ok(LiteralType::Boolean(x.is_infinite()))
+    });
+
+    // isInteger(x) is false for NaN and infinities
+    define_native(globals, "isInteger", 1, |interpreter, args, paren| {
+        let x = expect_number(&args[0], paren, &interpreter.reporter)?;
+        Ok(LiteralType::Boolean(x.fract() == 0.0))
+    });
+
+    // parseInt(s) parses a decimal integer, parseInt(s, radix) one in base 2 to 36,
+    // both are nil if s isn't one
+    define_native_range(globals, "parseInt", 1..=2, |interpreter, args, paren| {
+        let s = expect_string(&args[0], paren, &interpreter.reporter)?;
+        let radix = match args.get(1) {
+            Some(radix) => expect_number(radix, paren, &interpreter.reporter)?,
+            None => 10.0,
+        };
+        if !(2.0..=36.0).contains(&radix) || radix.fract() != 0.0 {
+            interpreter.reporter.error(
+                paren.line,
+                &format!("Radix must be an integer from 2 to 36, got {radix}."),
+            );
+            return Err(Exit::RuntimeError);
+        }
+        Ok(i64::from_str_radix(s, radix as u32)
+            .map_or(LiteralType::Nil, |n| LiteralType::Number(n as f64)))
+    });
+
+    // parseFloat(s) is the number s represents or nil if it isn't one
+    define_native(globals, "parseFloat", 1, |interpreter, args, paren| {
+        let s = expect_string(&args[0], paren, &interpreter.reporter)?;
+        Ok(s.parse().map_or(LiteralType::Nil, LiteralType::Number))
+    });
+}
+
+fn define_instance_functions(globals: &mut Environment) {
+    // copy(instance) creates a shallow copy: it has the same class and its fields
+    // refer to the same values, but setting fields doesn't affect the original
+    define_native(globals, "copy", 1, |interpreter, args, paren| {
+        let copy =
+            LoxInstance::clone(&expect_instance(&args[0], paren, &interpreter.reporter)?.borrow());
+        Ok(LiteralType::Callable(Callable::Instance(Rc::new(
+            RefCell::new(copy),
+        ))))
+    });
+}
+
+fn define_debug_functions(globals: &mut Environment) {
+    // debug(value) prints the internal representation of value, e.g. the fields of instances
+    define_native(globals, "debug", 1, |_, args, _| {
+        println!("{:?}", args[0]);
+        Ok(LiteralType::Nil)
+    });
+
+    // mro(class) lists the names of the classes methods are looked up in, starting with class
+    // itself and followed by its superclasses
+    define_native(globals, "mro", 1, |interpreter, args, paren| {
+        let class = expect_class(&args[0], paren, &interpreter.reporter)?;
+        let names =
+            std::iter::successors(Some(class.as_ref()), |class| class.superclass.as_deref())
+                .map(|class| LiteralType::String(class.name.as_str().into()))
+                .collect();
+        Ok(new_list(names))
+    });
+
+    // vars() lists the names defined in the global environment
+    define_native(globals, "vars", 0, |interpreter, _, _| {
+        let mut names: Vec<String> = interpreter
+            .globals
+            .borrow()
+            .values
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+        Ok(new_list(
+            names
+                .into_iter()
+                .map(|name| LiteralType::String(name.into()))
+                .collect(),
+        ))
+    });
+}
+
+/// Natives touching the file system or environment, these aren't defined for sandboxed
+/// interpreters
+pub fn define_file_functions(globals: &mut Environment) {
+    // readFile(path) is the content of the file or nil if it can't be read
+    define_native(globals, "readFile", 1, |interpreter, args, paren| {
+        let path = expect_string(&args[0], paren, &interpreter.reporter)?;
+        Ok(
+            fs::read_to_string(path).map_or(LiteralType::Nil, |content| {
+                LiteralType::String(content.into())
+            }),
+        )
+    });
+    // writeFile(path, contents) replaces the file's content and returns whether that worked
+    define_native(globals, "writeFile", 2, |interpreter, args, paren| {
+        let path = expect_string(&args[0], paren, &interpreter.reporter)?;
+        let contents = expect_string(&args[1], paren, &interpreter.reporter)?;
+        Ok(LiteralType::Boolean(fs::write(path, contents).is_ok()))
+    });
+    // getenv(name) is the value of the environment variable or nil if it isn't set
+    define_native(globals, "getenv", 1, |interpreter, args, paren| {
+        let name = expect_string(&args[0], paren, &interpreter.reporter)?;
+        Ok(std::env::var(name).map_or(LiteralType::Nil, |value| LiteralType::String(value.into())))
+    });
+}
+
+/// Built-in methods of strings, numbers and lists, e.g. `"abc".upper()`, bound to `object`
+pub fn primitive_method(object: &LiteralType, name: &str) -> Option<NativeFunction> {
+    let callable: NativeFn = match (object, name) {
+        (LiteralType::String(_), "upper") => |_, args, _| {
+            Ok(LiteralType::String(
+                receiver_string(&args[0]).to_uppercase().into(),
+            ))
+        },
+        (LiteralType::String(_), "lower") => |_, args, _| {
+            Ok(LiteralType::String(
+                receiver_string(&args[0]).to_lowercase().into(),
+            ))
+        },
+        (LiteralType::String(_), "length") => |_, args, _| {
+            Ok(LiteralType::Number(
+                receiver_string(&args[0]).chars().count() as f64,
+            ))
+        },
+        (LiteralType::String(_), "trim") => {
+            |_, args, _| Ok(LiteralType::String(receiver_string(&args[0]).trim().into()))
+        }
+        (LiteralType::Number(_), "floor") => {
+            |_, args, _| Ok(LiteralType::Number(receiver_number(&args[0]).floor()))
+        }
+        (LiteralType::Number(_), "ceil") => {
+            |_, args, _| Ok(LiteralType::Number(receiver_number(&args[0]).ceil()))
+        }
+        (LiteralType::Number(_), "abs") => {
+            |_, args, _| Ok(LiteralType::Number(receiver_number(&args[0]).abs()))
+        }
+        (LiteralType::List(_), "size") => {
+            |_, args, _| Ok(LiteralType::Number(receiver_list(&args[0]).len() as f64))
+        }
+        _ => return None,
+    };
+    Some(NativeFunction {
+        arity: 0..=0,
+        callable,
+        receiver: Some(Box::new(object.clone())),
+    })
+}
+
+// Receivers of primitive methods always have the type the method was looked up on
+fn receiver_string(receiver: &LiteralType) -> &str {
+    match receiver {
+        LiteralType::String(s) => s,
+        _ => unreachable!("string method bound to {receiver:?}"),
+    }
+}
+
+fn receiver_number(receiver: &LiteralType) -> f64 {
+    match receiver {
+        LiteralType::Number(n) => *n,
+        _ => unreachable!("number method bound to {receiver:?}"),
+    }
+}
+
+fn receiver_list(receiver: &LiteralType) -> std::cell::Ref<'_, Vec<LiteralType>> {
+    match receiver {
+        LiteralType::List(list) => list.borrow(),
+        _ => unreachable!("list method bound to {receiver:?}"),
+    }
+}
@@ -1,29 +1,47 @@
 use crate::{expr::Expr, token::Token};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Stmt {
     Block(Block),
+    Break(Break),
     Class(Class),
     Expression(Expression),
     If(If),
+    Import(Import),
     Var(Var),
+    MultiVar(MultiVar),
     While(While),
+    ForRange(ForRange),
+    ForEach(ForEach),
     Function(Function),
     Return(Return),
+    Try(Try),
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Return {
     pub keyword: Token,
     pub value: Box<Expr>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Block {
     pub statements: Vec<Stmt>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Break {
+    pub keyword: Token,
+    /// Label of the loop to leave, the innermost one if there is none
+    pub label: Option<Token>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Class {
     pub name: Token,
     pub superclass: Option<Expr>,
@@ -31,58 +49,154 @@ pub struct Class {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Function {
     pub name: Token,
-    pub params: Vec<Token>,
+    /// Parameter names with their default values, only trailing ones can have one
+    pub params: Vec<(Token, Option<Expr>)>,
+    /// `...name` after the parameters collecting the remaining arguments in a list
+    pub rest: Option<Token>,
     pub body: Vec<Stmt>,
 }
 
+impl Function {
+    /// Number of parameters without a default
+    pub fn arity(&self) -> usize {
+        self.params
+            .iter()
+            .filter(|(_, default)| default.is_none())
+            .count()
+    }
+
+    /// `None` if there is a rest parameter
+    pub fn max_arity(&self) -> Option<usize> {
+        if self.rest.is_some() {
+            None
+        } else {
+            Some(self.params.len())
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct If {
     pub condition: Box<Expr>,
     pub then_branch: Box<Stmt>,
     pub else_branch: Option<Box<Stmt>>,
 }
 
+/// Statements of an imported file which get spliced in where the import is
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Import {
+    /// Path as written in the import
+    pub file: String,
+    pub statements: Vec<Stmt>,
+}
+
+/// `try { body } catch (name) { handler }`, the handler runs with the thrown value bound to `name`
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Try {
+    pub body: Vec<Stmt>,
+    pub name: Token,
+    pub handler: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Var {
     pub name: Token,
     pub initializer: Box<Expr>,
 }
 
+/// `var a = 1, b;`, the variables are declared one after another
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MultiVar {
+    pub vars: Vec<Var>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct While {
+    pub keyword: Token,
+    /// Name the loop is given with `name: while ...` for `break name;`
+    pub label: Option<Token>,
     pub condition: Box<Expr>,
     pub body: Box<Stmt>,
 }
 
+/// `for (i in start..end..step)`, counts from `start` up to but excluding `end`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ForRange {
+    pub keyword: Token,
+    pub label: Option<Token>,
+    pub name: Token,
+    pub start: Box<Expr>,
+    pub end: Box<Expr>,
+    pub step: Option<Box<Expr>>,
+    pub body: Box<Stmt>,
+}
+
+/// `for (x in iterable)`, runs the body for each element of a list or each value an
+/// instance's iterator gives
+///
+/// An instance is iterable if its class has an `iter()` method returning an iterator, which
+/// is an instance with `hasNext()` and `next()` methods. Each step calls `hasNext()` and,
+/// if that's truthy, `next()` for the value of `x`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ForEach {
+    pub keyword: Token,
+    pub label: Option<Token>,
+    pub name: Token,
+    pub iterable: Box<Expr>,
+    pub body: Box<Stmt>,
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Expression {
     pub expr: Box<Expr>,
 }
 
 pub trait Visitor<T> {
     fn visit_block(&mut self, stmt: &Block) -> T;
+    fn visit_break(&mut self, stmt: &Break) -> T;
     fn visit_class(&mut self, stmt: &Class) -> T;
     fn visit_expression(&mut self, stmt: &Expression) -> T;
     fn visit_if(&mut self, stmt: &If) -> T;
+    fn visit_import(&mut self, stmt: &Import) -> T;
     fn visit_var(&mut self, stmt: &Var) -> T;
+    fn visit_multi_var(&mut self, stmt: &MultiVar) -> T;
     fn visit_while(&mut self, stmt: &While) -> T;
+    fn visit_for_range(&mut self, stmt: &ForRange) -> T;
+    fn visit_for_each(&mut self, stmt: &ForEach) -> T;
     fn visit_function(&mut self, stmt: &Function) -> T;
     fn visit_return(&mut self, expr: &Return) -> T;
+    fn visit_try(&mut self, stmt: &Try) -> T;
 }
 
 impl Stmt {
     pub fn accept<T>(&self, visitor: &mut dyn Visitor<T>) -> T {
         match self {
             Stmt::Block(block) => visitor.visit_block(block),
+            Stmt::Break(break_stmt) => visitor.visit_break(break_stmt),
             Stmt::Class(class) => visitor.visit_class(class),
             Stmt::Expression(expression) => visitor.visit_expression(expression),
             Stmt::If(if_stmt) => visitor.visit_if(if_stmt),
+            Stmt::Import(import) => visitor.visit_import(import),
             Stmt::Var(print) => visitor.visit_var(print),
+            Stmt::MultiVar(multi_var) => visitor.visit_multi_var(multi_var),
             Stmt::While(while_stmt) => visitor.visit_while(while_stmt),
+            Stmt::ForRange(for_range) => visitor.visit_for_range(for_range),
+            Stmt::ForEach(for_each) => visitor.visit_for_each(for_each),
             Stmt::Function(func) => visitor.visit_function(func),
             Stmt::Return(ret) => visitor.visit_return(ret),
+            Stmt::Try(try_stmt) => visitor.visit_try(try_stmt),
         }
     }
 }
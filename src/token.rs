@@ -1,12 +1,20 @@
-use crate::lox_callable::{Callable, NativeFunction};
+use crate::lox_callable::{Callable, FieldsInOrder, NativeFunction};
+use std::cell::RefCell;
 use std::fmt::Display;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
     pub literal: LiteralType,
     pub line: usize,
+    /// 1-based column of the token's first character, 0 for tokens the scanner didn't make
+    pub column: usize,
+    /// Comments right before the token, only kept if the scanner was asked to
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub leading_comments: Vec<String>,
 }
 
 impl Token {
@@ -16,6 +24,8 @@ impl Token {
             lexeme,
             literal,
             line,
+            column: 0,
+            leading_comments: Vec::new(),
         }
     }
 }
@@ -26,52 +36,146 @@ impl Display for Token {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub enum LiteralType {
-    String(String),
+    String(Rc<str>),
     Number(f64),
+    /// Exact integer written with an `n` suffix like `123n`
+    #[cfg(feature = "bigint")]
+    BigInt(num_bigint::BigInt),
     Nil,
     Boolean(bool),
     Callable(Callable),
     NativeFunction(NativeFunction),
+    List(Rc<RefCell<Vec<LiteralType>>>),
+}
+
+impl LiteralType {
+    /// Like the `Display` output but with strings quoted and the type named, used for error messages
+    pub fn to_debug_string(&self) -> String {
+        match self {
+            LiteralType::String(val) => format!("string {val:?}"),
+            LiteralType::Number(_) => format!("number {self}"),
+            #[cfg(feature = "bigint")]
+            LiteralType::BigInt(_) => format!("bigint {self}"),
+            LiteralType::Boolean(_) => format!("boolean {self}"),
+            LiteralType::List(_) => format!("list {self}"),
+            _ => self.to_string(),
+        }
+    }
+}
+
+thread_local! {
+    /// Lists and instances currently being formatted, to notice when one contains itself
+    static FORMATTING: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Formats the list or instance at `ptr` with `format`, or as `<circular>` if it's
+/// already being formatted further up because it contains itself
+pub(crate) fn format_once(
+    ptr: *const (),
+    f: &mut std::fmt::Formatter<'_>,
+    format: impl FnOnce(&mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+) -> std::fmt::Result {
+    if FORMATTING.with_borrow(|formatting| formatting.contains(&ptr)) {
+        return write!(f, "<circular>");
+    }
+    FORMATTING.with_borrow_mut(|formatting| formatting.push(ptr));
+    let result = format(f);
+    FORMATTING.with_borrow_mut(|formatting| formatting.pop());
+    result
+}
+
+impl std::fmt::Debug for LiteralType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LiteralType::String(val) => f.debug_tuple("String").field(val).finish(),
+            LiteralType::Number(val) => f.debug_tuple("Number").field(val).finish(),
+            #[cfg(feature = "bigint")]
+            LiteralType::BigInt(val) => f.debug_tuple("BigInt").field(val).finish(),
+            LiteralType::Nil => write!(f, "Nil"),
+            LiteralType::Boolean(val) => f.debug_tuple("Boolean").field(val).finish(),
+            LiteralType::Callable(val) => f.debug_tuple("Callable").field(val).finish(),
+            LiteralType::NativeFunction(val) => f.debug_tuple("NativeFunction").field(val).finish(),
+            LiteralType::List(list) => format_once(Rc::as_ptr(list).cast(), f, |f| {
+                f.debug_tuple("List").field(&*list.borrow()).finish()
+            }),
+        }
+    }
 }
 
+/// Literals in the AST serialize as the JSON value, runtime values as their display string
+#[cfg(feature = "serde")]
+impl serde::Serialize for LiteralType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            LiteralType::String(val) => serializer.serialize_str(val),
+            LiteralType::Number(val) => serializer.serialize_f64(*val),
+            LiteralType::Nil => serializer.serialize_unit(),
+            LiteralType::Boolean(val) => serializer.serialize_bool(*val),
+            other => serializer.serialize_str(&other.to_string()),
+        }
+    }
+}
+
+/// Numbers are printed without a trailing `.0` and in scientific notation like `1e20`
+/// if they're very large or small, strings without quotes
+/// and functions and classes as `<fn name>` and `<class name>`
 impl Display for LiteralType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             LiteralType::String(val) => write!(f, "{val}"),
+            LiteralType::Number(val) if val.abs() >= 1e15 || (*val != 0.0 && val.abs() < 1e-4) => {
+                write!(f, "{val:e}")
+            }
             LiteralType::Number(val) => write!(f, "{val}"),
+            #[cfg(feature = "bigint")]
+            LiteralType::BigInt(val) => write!(f, "{val}"),
             LiteralType::Nil => write!(f, "nil"),
-            LiteralType::Boolean(val) => write!(f, "\"{val}\""),
-            LiteralType::Callable(Callable::Class(x)) => {
-                write!(f, "Class: {}", x)
-            }
+            LiteralType::Boolean(val) => write!(f, "{val}"),
+            LiteralType::Callable(Callable::Function(x)) => write!(f, "{x}"),
+            LiteralType::Callable(Callable::Class(x)) => write!(f, "<class {x}>"),
             LiteralType::Callable(Callable::Instance(x)) => {
-                write!(
-                    f,
-                    "Instance of class: {}, fields set: {:?}",
-                    x.borrow().class,
-                    x.borrow().fields
-                )
+                format_once(Rc::as_ptr(x).cast(), f, |f| {
+                    write!(
+                        f,
+                        "Instance of class: {}, fields set: {:?}",
+                        x.borrow().class,
+                        FieldsInOrder(&x.borrow())
+                    )
+                })
             }
-            LiteralType::Callable(_) => write!(f, "<callable>"),
-            LiteralType::NativeFunction(_) => write!(f, "<native fn>"),
+            LiteralType::NativeFunction(x) => write!(f, "{x}"),
+            LiteralType::List(list) => format_once(Rc::as_ptr(list).cast(), f, |f| {
+                write!(f, "[")?;
+                for (i, element) in list.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{element}")?;
+                }
+                write!(f, "]")
+            }),
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TokenType {
     // Single-character tokens
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
     Plus,
     Semicolon,
+    Colon,
     Slash,
     Star,
     Percentage,
@@ -88,26 +192,39 @@ pub enum TokenType {
 
     AmperAmper,
     BarBar,
+    QuestionQuestion,
+    QuestionDot,
+    DotDot,
+    DotDotDot,
 
     // Literals.
     Identifier,
     String,
+    /// The part of a string before a `${`, the end of the string is scanned as a `String`
+    Interpolation,
     Number,
 
     // Keywords.
     And,
+    Break,
+    Catch,
     Class,
+    Div,
+    Elif,
     Else,
     False,
     Fun,
     For,
     If,
+    Import,
+    In,
     Nil,
     Or,
     Return,
     SuperKW,
     SelfKW,
     True,
+    Try,
     Var,
     While,
 
@@ -115,49 +232,66 @@ pub enum TokenType {
 }
 
 impl Display for TokenType {
+    /// Human-readable name used in error messages, e.g. "right paren" or "keyword 'while'"
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            TokenType::BarBar => write!(f, "||"),
-            TokenType::AmperAmper => write!(f, "&&"),
-            TokenType::LeftParen => write!(f, "LeftParen"),
-            TokenType::RightParen => write!(f, "RightParen"),
-            TokenType::LeftBrace => write!(f, "LeftBrace"),
-            TokenType::RightBrace => write!(f, "RightBrace"),
-            TokenType::Comma => write!(f, "Comma"),
-            TokenType::Dot => write!(f, "Dot"),
-            TokenType::Minus => write!(f, "Minus"),
-            TokenType::Plus => write!(f, "Plus"),
-            TokenType::Semicolon => write!(f, "Semicolon"),
-            TokenType::Slash => write!(f, "Slash"),
-            TokenType::Star => write!(f, "Star"),
-            TokenType::Percentage => write!(f, "Percentage"),
-            TokenType::Bang => write!(f, "Bang"),
-            TokenType::BangEqual => write!(f, "BangEqual"),
-            TokenType::Equal => write!(f, "Equal"),
-            TokenType::EqualEqual => write!(f, "EqualEqual"),
-            TokenType::Greater => write!(f, "Greater"),
-            TokenType::GreaterEqual => write!(f, "GreaterEqual"),
-            TokenType::Less => write!(f, "Less"),
-            TokenType::LessEqual => write!(f, "LessEqual"),
-            TokenType::Identifier => write!(f, "Identifier"),
-            TokenType::String => write!(f, "String"),
-            TokenType::Number => write!(f, "Number"),
-            TokenType::And => write!(f, "And"),
-            TokenType::Class => write!(f, "Class"),
-            TokenType::Else => write!(f, "Else"),
-            TokenType::False => write!(f, "False"),
-            TokenType::Fun => write!(f, "Fun"),
-            TokenType::For => write!(f, "For"),
-            TokenType::If => write!(f, "If"),
-            TokenType::Nil => write!(f, "Nil"),
-            TokenType::Or => write!(f, "Or"),
-            TokenType::Return => write!(f, "Return"),
-            TokenType::SuperKW => write!(f, "Super"),
-            TokenType::SelfKW => write!(f, "Self"),
-            TokenType::True => write!(f, "True"),
-            TokenType::Var => write!(f, "Var"),
-            TokenType::While => write!(f, "While"),
-            TokenType::Eof => write!(f, "Eof"),
-        }
+        let name = match self {
+            TokenType::BarBar => "'||'",
+            TokenType::AmperAmper => "'&&'",
+            TokenType::QuestionQuestion => "'??'",
+            TokenType::QuestionDot => "'?.'",
+            TokenType::LeftParen => "left paren",
+            TokenType::RightParen => "right paren",
+            TokenType::LeftBrace => "left brace",
+            TokenType::RightBrace => "right brace",
+            TokenType::LeftBracket => "left bracket",
+            TokenType::RightBracket => "right bracket",
+            TokenType::Comma => "comma",
+            TokenType::Dot => "dot",
+            TokenType::DotDot => "'..'",
+            TokenType::DotDotDot => "'...'",
+            TokenType::Minus => "minus",
+            TokenType::Plus => "plus",
+            TokenType::Semicolon => "semicolon",
+            TokenType::Colon => "colon",
+            TokenType::Slash => "slash",
+            TokenType::Star => "star",
+            TokenType::Percentage => "percent sign",
+            TokenType::Bang => "bang",
+            TokenType::BangEqual => "'!='",
+            TokenType::Equal => "equals sign",
+            TokenType::EqualEqual => "'=='",
+            TokenType::Greater => "'>'",
+            TokenType::GreaterEqual => "'>='",
+            TokenType::Less => "'<'",
+            TokenType::LessEqual => "'<='",
+            TokenType::Identifier => "identifier",
+            TokenType::String => "string",
+            TokenType::Interpolation => "string interpolation",
+            TokenType::Number => "number",
+            TokenType::And => "keyword 'and'",
+            TokenType::Break => "keyword 'break'",
+            TokenType::Catch => "keyword 'catch'",
+            TokenType::Class => "keyword 'class'",
+            TokenType::Div => "keyword 'div'",
+            TokenType::Elif => "keyword 'elif'",
+            TokenType::Else => "keyword 'else'",
+            TokenType::False => "keyword 'false'",
+            TokenType::Fun => "keyword 'fun'",
+            TokenType::For => "keyword 'for'",
+            TokenType::If => "keyword 'if'",
+            TokenType::Import => "keyword 'import'",
+            TokenType::In => "keyword 'in'",
+            TokenType::Nil => "keyword 'nil'",
+            TokenType::Or => "keyword 'or'",
+            TokenType::Return => "keyword 'return'",
+            TokenType::SuperKW => "keyword 'super'",
+            TokenType::SelfKW => "keyword 'self'",
+            TokenType::True => "keyword 'true'",
+            TokenType::Try => "keyword 'try'",
+            TokenType::Var => "keyword 'var'",
+            TokenType::While => "keyword 'while'",
+            TokenType::Eof => "end of file",
+        };
+        write!(f, "{name}")
     }
 }
@@ -0,0 +1,194 @@
+//! Embedding the interpreter through the library
+
+use std::{path::Path, rc::Rc};
+
+use jlox_rs::{
+    ErrorReporter, Interpreter, InterpreterBuilder, LiteralType, Severity, run_source,
+    scan_with_comments,
+};
+
+/// An interpreter collecting its errors instead of printing them, which can return
+/// values from the top level
+fn builder() -> InterpreterBuilder {
+    InterpreterBuilder::new()
+        .error_reporter(ErrorReporter::quiet())
+        .allow_top_level_return()
+}
+
+/// Messages of the errors and warnings reported so far
+fn messages(interpreter: &Interpreter) -> Vec<String> {
+    interpreter
+        .reporter
+        .diagnostics()
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect()
+}
+
+#[test]
+fn write_and_read_file() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("api_write_and_read.txt");
+    let path = path.to_str().unwrap();
+    let mut interpreter = builder().build();
+
+    let result = run_source(
+        &mut interpreter,
+        &format!(
+            r#"
+            var written = writeFile("{path}", "line 1\nline 2");
+            return [written, readFile("{path}"), readFile("{path}.missing")];
+            "#
+        ),
+    );
+    assert_eq!(result.unwrap().to_string(), "[true, line 1\nline 2, nil]");
+    assert_eq!(std::fs::read_to_string(path).unwrap(), "line 1\nline 2");
+}
+
+#[test]
+fn sandboxed_interpreter_has_no_file_or_environment_access() {
+    let mut interpreter = builder().sandboxed().build();
+    assert!(run_source(&mut interpreter, r#"readFile("Cargo.toml");"#).is_none());
+    assert_eq!(messages(&interpreter), ["Undefinied variable readFile."]);
+
+    let mut interpreter = builder().sandboxed().build();
+    assert!(run_source(&mut interpreter, r#"writeFile("x", "y");"#).is_none());
+    assert_eq!(messages(&interpreter), ["Undefinied variable writeFile."]);
+
+    let mut interpreter = builder().sandboxed().build();
+    assert!(run_source(&mut interpreter, r#"getenv("HOME");"#).is_none());
+    assert_eq!(messages(&interpreter), ["Undefinied variable getenv."]);
+}
+
+#[test]
+fn comments_are_kept_on_the_next_token() {
+    let tokens = scan_with_comments("// greeting\n// twice\nprint(\"hi\"); // trailing\nvar x;");
+    let with_comments: Vec<(&str, &[String])> = tokens
+        .iter()
+        .filter(|token| !token.leading_comments.is_empty())
+        .map(|token| (token.lexeme.as_str(), &token.leading_comments[..]))
+        .collect();
+    assert_eq!(
+        with_comments,
+        [
+            (
+                "print",
+                &["// greeting".to_owned(), "// twice".to_owned()][..]
+            ),
+            ("var", &["// trailing".to_owned()][..]),
+        ]
+    );
+    assert_eq!(tokens[0].column, 1);
+}
+
+#[test]
+fn identifiers_can_contain_digits_but_not_start_with_them() {
+    let tokens = scan_with_comments("abc123 a1b2 2abc");
+    let scanned: Vec<(String, &str)> = tokens
+        .iter()
+        .map(|token| (format!("{:?}", token.token_type), token.lexeme.as_str()))
+        .collect();
+    let expected = [
+        ("Identifier", "abc123"),
+        ("Identifier", "a1b2"),
+        ("Number", "2"),
+        ("Identifier", "abc"),
+        ("Eof", ""),
+    ];
+    assert_eq!(
+        scanned,
+        expected.map(|(token_type, lexeme)| (token_type.to_owned(), lexeme))
+    );
+    assert!(matches!(tokens[2].literal, LiteralType::Number(n) if n == 2.0));
+}
+
+#[test]
+fn errors_of_every_phase_are_collected() {
+    let mut interpreter = builder().build();
+    assert!(run_source(&mut interpreter, "var a = 1 @ 2;\nprint(a;").is_none());
+    let diagnostics: Vec<_> = interpreter
+        .reporter
+        .diagnostics()
+        .into_iter()
+        .map(|d| (d.line, d.column, d.severity, d.message))
+        .collect();
+    assert_eq!(
+        diagnostics,
+        [
+            (
+                1,
+                Some(11),
+                Severity::Error,
+                "Unexpected Character '@' at column 11".to_owned()
+            ),
+            (
+                1,
+                Some(9),
+                Severity::Error,
+                "at '1' Expect semicolon, found number '2' instead.".to_owned()
+            ),
+            (
+                2,
+                Some(7),
+                Severity::Error,
+                "at 'a' Expect ')' after arguments, found semicolon instead.".to_owned()
+            ),
+        ]
+    );
+
+    interpreter.reporter.clear();
+    assert!(!interpreter.reporter.had_error());
+    assert!(run_source(&mut interpreter, "{ var b = b; }").is_none());
+    assert_eq!(
+        messages(&interpreter),
+        ["Can't read local variable in its own initializer."]
+    );
+
+    interpreter.reporter.clear();
+    assert!(run_source(&mut interpreter, "return 1 + nil;").is_none());
+    assert_eq!(
+        messages(&interpreter),
+        ["Both operands of '+' must be a number or string, got number 1, nil"]
+    );
+    assert!(interpreter.reporter.had_error());
+}
+
+#[test]
+fn execution_limit_stops_infinite_loops() {
+    let mut interpreter = builder().execution_limit(100).build();
+    assert!(run_source(&mut interpreter, "var forever = true; while (forever) {}").is_none());
+    assert_eq!(messages(&interpreter), ["Execution limit exceeded."]);
+
+    // every run gets the whole limit again
+    interpreter.reporter.clear();
+    let result = run_source(
+        &mut interpreter,
+        "var i = 0; while (i < 100) i = i + 1; return i;",
+    );
+    assert_eq!(result.unwrap().to_string(), "100");
+    assert!(run_source(&mut interpreter, "fun f() { f(); } f();").is_none());
+    assert_eq!(messages(&interpreter), ["Execution limit exceeded."]);
+}
+
+#[test]
+fn copied_strings_share_their_contents() {
+    let mut interpreter = builder().build();
+    let result = run_source(
+        &mut interpreter,
+        r#"var s = "long string"; var t = s; fun id(x) { return x; } return [s, t, id(s), s + ""];"#,
+    );
+    let Some(LiteralType::List(list)) = result else {
+        panic!("expected a list, got {result:?}");
+    };
+    let strings: Vec<_> = list
+        .borrow()
+        .iter()
+        .map(|element| match element {
+            LiteralType::String(s) => Rc::clone(s),
+            other => panic!("expected a string, got {other:?}"),
+        })
+        .collect();
+    assert!(Rc::ptr_eq(&strings[0], &strings[1]));
+    assert!(Rc::ptr_eq(&strings[0], &strings[2]));
+    assert_eq!(&*strings[3], "long string");
+    assert!(!Rc::ptr_eq(&strings[0], &strings[3]));
+}
@@ -0,0 +1,32 @@
+//! Helpers for the tests running the interpreter binary
+
+// every test crate compiles its own copy and only uses some of them
+#![allow(dead_code)]
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+/// Runs `source` piped to the interpreter
+pub fn run_stdin(source: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_jlox-rs"))
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(source.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// Stderr of running `source` piped to the interpreter
+pub fn stderr_of(source: &str) -> String {
+    String::from_utf8(run_stdin(source).stderr).unwrap()
+}
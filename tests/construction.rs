@@ -0,0 +1,53 @@
+//! Constructing instances checks the arguments against the initializer, also an
+//! inherited one, and reports the line of the call
+
+mod common;
+
+use common::{run_stdin, stderr_of};
+
+const CLASSES: &str = "
+class NoInit {}
+class WithNew { new(a, b) { self.a = a; } }
+class WithInit { init(a) { self.a = a; } }
+class Sub < WithNew {}
+";
+
+#[test]
+fn right_number_of_arguments() {
+    let output = run_stdin(&format!(
+        "{CLASSES}
+print(NoInit() != nil);
+print(WithNew(1, 2).a);
+print(WithInit(3).a);
+print(Sub(4, 5).a);"
+    ));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "true\n1\n3\n4\n");
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+}
+
+#[test]
+fn wrong_number_of_arguments() {
+    for (call, expected) in [
+        ("NoInit(1);", "Expected 0 arguments but got 1."),
+        ("WithNew(1);", "Expected 2 arguments but got 1."),
+        ("WithNew(1, 2, 3);", "Expected 2 arguments but got 3."),
+        ("WithInit();", "Expected 1 arguments but got 0."),
+        ("Sub(1);", "Expected 2 arguments but got 1."),
+        ("var c = NoInit; c(1);", "Expected 0 arguments but got 1."),
+    ] {
+        // the classes take up lines 1 to 5
+        assert_eq!(
+            stderr_of(&format!("{CLASSES}{call}")),
+            format!("[line 6] Error : {expected}\nRuntime Error\n"),
+            "{call}"
+        );
+    }
+}
+
+#[test]
+fn error_line_is_the_closing_paren() {
+    assert_eq!(
+        stderr_of(&format!("{CLASSES}WithNew(\n  1,\n  2,\n  3\n);")),
+        "[line 10] Error : Expected 2 arguments but got 3.\nRuntime Error\n"
+    );
+}
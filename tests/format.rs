@@ -0,0 +1,30 @@
+//! Formatting a script has to give one that runs the same and formats to itself
+
+use std::{fs, path::Path, process::Command};
+
+fn jlox(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_jlox-rs"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.stderr.is_empty(),
+        "{args:?} failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn format_round_trip() {
+    let original = "tests/scripts/format/roundtrip.lox";
+    let formatted = jlox(&["fmt", original]);
+
+    let copy = Path::new(env!("CARGO_TARGET_TMPDIR")).join("roundtrip.lox");
+    fs::write(&copy, &formatted).unwrap();
+    let copy = copy.to_str().unwrap();
+
+    assert_eq!(jlox(&["fmt", copy]), formatted);
+    assert_eq!(jlox(&[copy]), jlox(&[original]));
+}
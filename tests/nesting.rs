@@ -0,0 +1,57 @@
+//! Pathologically nested programs have to fail with a parse error instead of
+//! overflowing the stack in one of the phases walking the tree
+
+mod common;
+
+/// Stdout and stderr of running `source`
+fn run(source: &str) -> (String, String) {
+    let output = common::run_stdin(source);
+    assert!(output.status.success(), "{:?}", output.status);
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+fn assert_too_deep(source: &str, at: &str) {
+    let (stdout, stderr) = run(source);
+    assert_eq!(stdout, "");
+    assert_eq!(
+        stderr,
+        format!("[line 1] Error :  at '{at}' Too deeply nested.\nParsing error\n")
+    );
+}
+
+#[test]
+fn nested_parentheses() {
+    let n = 5000;
+    assert_too_deep(&format!("print({}1{});", "(".repeat(n), ")".repeat(n)), "(");
+    assert_too_deep(&format!("print({}1{});", "[".repeat(n), "]".repeat(n)), "[");
+}
+
+#[test]
+fn long_chains() {
+    let n = 200_000;
+    assert_too_deep(&format!("print({});", vec!["1"; n].join(" + ")), "1");
+    assert_too_deep(
+        &format!("print({});", vec!["true"; n].join(" and ")),
+        "true",
+    );
+    assert_too_deep(
+        &format!("fun f() {{ return f; }} f{};", "()".repeat(n)),
+        "(",
+    );
+}
+
+#[test]
+fn nesting_below_the_limit() {
+    let n = 80;
+    assert_eq!(
+        run(&format!("print({}1{});", "(".repeat(n), ")".repeat(n))),
+        ("1\n".to_owned(), String::new())
+    );
+    assert_eq!(
+        run(&format!("print({});", vec!["1"; n].join(" + "))),
+        (format!("{n}\n"), String::new())
+    );
+}
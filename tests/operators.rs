@@ -0,0 +1,83 @@
+//! Errors of operators and natives name what they got, which scripts can't check for more
+//! than one case since the first error ends them
+
+mod common;
+
+use common::stderr_of;
+
+#[test]
+fn comparing_nil() {
+    for operator in ["<", "<=", ">", ">="] {
+        assert_eq!(
+            stderr_of(&format!("print(nil {operator} 5);")),
+            format!("[line 1] Error : Can't compare nil {operator} number 5\nRuntime Error\n")
+        );
+        assert_eq!(
+            stderr_of(&format!("print(5 {operator} nil);")),
+            format!("[line 1] Error : Can't compare number 5 {operator} nil\nRuntime Error\n")
+        );
+    }
+}
+
+#[test]
+fn arithmetic_with_wrong_types() {
+    assert_eq!(
+        stderr_of("print(true + nil);"),
+        "[line 1] Error : Both operands of '+' must be a number or string, got boolean true, nil\nRuntime Error\n"
+    );
+    for operator in ["-", "*", "/", "%", "div"] {
+        assert_eq!(
+            stderr_of(&format!("print(true {operator} nil);")),
+            format!(
+                "[line 1] Error : Both operands of '{operator}' must be a number, got boolean true, nil\nRuntime Error\n"
+            )
+        );
+    }
+}
+
+#[test]
+fn natives_with_wrong_types() {
+    for call in [
+        "abs(\"x\")",
+        "sign(\"x\")",
+        "pow(2, \"x\")",
+        "clamp(1, 0, \"x\")",
+    ] {
+        assert_eq!(
+            stderr_of(&format!("print({call});")),
+            "[line 1] Error : Expected a number, got string \"x\".\nRuntime Error\n",
+            "{call}"
+        );
+    }
+    for call in ["map(1, abs)", "sort(1)", "contains(1, 1)", "remove(1, 1)"] {
+        assert_eq!(
+            stderr_of(&format!("print({call});")),
+            "[line 1] Error : Expected a list, got number 1.\nRuntime Error\n",
+            "{call}"
+        );
+    }
+    assert_eq!(
+        stderr_of("print(parseFloat(1));"),
+        "[line 1] Error : Expected a string, got number 1.\nRuntime Error\n"
+    );
+}
+
+#[test]
+fn comparisons_with_wrong_types() {
+    let error = "[line 1] Error : Can't compare string \"a\" <= number 2\nRuntime Error\n";
+    assert_eq!(stderr_of("print(\"a\" <= 2);"), error);
+    assert_eq!(stderr_of("print(between(2, \"a\", 3));"), error);
+    #[cfg(feature = "bigint")]
+    assert_eq!(
+        stderr_of("print(2n > \"a\");"),
+        "[line 1] Error : Can't compare bigint 2 > string \"a\"\nRuntime Error\n"
+    );
+}
+
+#[test]
+fn negating_wrong_type() {
+    assert_eq!(
+        stderr_of("print(-\"a\");"),
+        "[line 1] Error : Operand of '-' must be a number, got string \"a\"\nRuntime Error\n"
+    );
+}
@@ -0,0 +1,149 @@
+//! Runs every script in `tests/scripts` and compares what it does with the expectations
+//! written in its comments:
+//!
+//! - `// expect: text` is the next line the script prints to stdout
+//! - `// stderr: text` is the next line it prints to stderr
+//! - `// exit: 65` is the exit code, 0 if there is none
+//! - `// run: --check {script} a b` are the arguments to run with, `{script}` by default
+//! - `// stdin: text` is a line of input
+//! - `// env: NAME=value` sets an environment variable
+//! - `// feature: bigint` skips the script unless the crate is built with that feature
+//!
+//! Files in subdirectories aren't run, they're for scripts importing or reading them.
+
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+#[derive(Default)]
+struct Expectations {
+    stdout: Vec<String>,
+    stderr: Vec<String>,
+    exit: i32,
+    args: Option<String>,
+    stdin: String,
+    env: Vec<(String, String)>,
+    features: Vec<String>,
+}
+
+fn parse_expectations(source: &str) -> Expectations {
+    let mut expectations = Expectations::default();
+    for line in source.lines() {
+        let Some((_, comment)) = line.split_once("// ") else {
+            continue;
+        };
+        let Some((directive, value)) = comment.split_once(": ") else {
+            continue;
+        };
+        match directive {
+            "expect" => expectations.stdout.push(value.to_owned()),
+            "stderr" => expectations.stderr.push(value.to_owned()),
+            "exit" => expectations.exit = value.parse().expect("exit code"),
+            "run" => expectations.args = Some(value.to_owned()),
+            "stdin" => {
+                expectations.stdin.push_str(value);
+                expectations.stdin.push('\n');
+            }
+            "env" => {
+                let (name, value) = value.split_once('=').expect("NAME=value");
+                expectations.env.push((name.to_owned(), value.to_owned()));
+            }
+            "feature" => expectations.features.push(value.to_owned()),
+            _ => {}
+        }
+    }
+    expectations
+}
+
+fn enabled(feature: &str) -> bool {
+    match feature {
+        "bigint" => cfg!(feature = "bigint"),
+        _ => panic!("unknown feature {feature}"),
+    }
+}
+
+/// Runs the script, returning a description of how it went wrong if it did
+fn run_script(path: &Path) -> Option<String> {
+    let source = fs::read_to_string(path).unwrap();
+    let expectations = parse_expectations(&source);
+    if !expectations.features.iter().all(|feature| enabled(feature)) {
+        return None;
+    }
+
+    // relative to the crate so messages mentioning the script are the same everywhere
+    let script = path
+        .strip_prefix(env!("CARGO_MANIFEST_DIR"))
+        .unwrap()
+        .to_str()
+        .unwrap();
+    let args = expectations.args.as_deref().unwrap_or("{script}");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_jlox-rs"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(
+            args.split_whitespace()
+                .map(|arg| arg.replace("{script}", script)),
+        )
+        .envs(expectations.env.iter().cloned())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(expectations.stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout: Vec<&str> = stdout.lines().collect();
+    let stderr: Vec<&str> = stderr.lines().collect();
+    let mut problems = Vec::new();
+    if stdout != expectations.stdout {
+        problems.push(format!(
+            "stdout:\n  expected {:?}\n  got      {stdout:?}",
+            expectations.stdout
+        ));
+    }
+    if stderr != expectations.stderr {
+        problems.push(format!(
+            "stderr:\n  expected {:?}\n  got      {stderr:?}",
+            expectations.stderr
+        ));
+    }
+    if output.status.code() != Some(expectations.exit) {
+        problems.push(format!(
+            "exit code: expected {}, got {:?}",
+            expectations.exit,
+            output.status.code()
+        ));
+    }
+    (!problems.is_empty()).then(|| format!("{script}\n{}", problems.join("\n")))
+}
+
+#[test]
+fn scripts() {
+    let mut paths: Vec<_> =
+        fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/scripts"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "lox"))
+            .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    let failures: Vec<String> = paths.iter().filter_map(|path| run_script(path)).collect();
+    assert!(
+        failures.is_empty(),
+        "{} of {} scripts failed:\n\n{}",
+        failures.len(),
+        paths.len(),
+        failures.join("\n\n")
+    );
+}